@@ -6,6 +6,7 @@ use std::{
 };
 
 use anyhow::{Context, bail};
+use tokio::sync::broadcast;
 
 /// `FMSAlarmType` indicates how the alarm will be displayed.
 /// `FMSAlarmType::Fault` will also activate the associated System Stop for the target_scope (LStop or EStop)
@@ -16,6 +17,25 @@ pub enum FMSAlarmType {
     Fault,
 }
 
+impl FMSAlarmType {
+    pub fn from_byte(integer: u8) -> FMSAlarmType {
+        match integer {
+            0 => FMSAlarmType::Info,
+            1 => FMSAlarmType::Warning,
+            2 => FMSAlarmType::Fault,
+            _ => FMSAlarmType::Fault,
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            FMSAlarmType::Info => 0,
+            FMSAlarmType::Warning => 1,
+            FMSAlarmType::Fault => 2,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct FMSAlarm {
     pub id: String,
@@ -29,6 +49,20 @@ pub struct FMSAlarm {
     pub auto_clear: bool,
 }
 
+/// What happened to the alarm carried by an `FMSAlarmEvent`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FMSAlarmEventType {
+    Thrown,
+    Released,
+    Cleared,
+}
+
+#[derive(Clone)]
+pub struct FMSAlarmEvent {
+    pub event_type: FMSAlarmEventType,
+    pub alarm: FMSAlarm,
+}
+
 pub struct RawFMSAlarmHandler {
     active_alarms: Vec<FMSAlarm>,
     historic_alarms: Vec<FMSAlarm>,
@@ -37,6 +71,7 @@ pub struct RawFMSAlarmHandler {
 #[derive(Clone)]
 pub struct FMSAlarmHandler {
     raw: Arc<RwLock<RawFMSAlarmHandler>>,
+    events: broadcast::Sender<FMSAlarmEvent>,
 }
 
 impl FMSAlarmHandler {
@@ -52,6 +87,11 @@ impl FMSAlarmHandler {
         raw.historic_alarms.clone()
     }
 
+    /// Receives an `FMSAlarmEvent` every time an alarm is thrown, released or cleared
+    pub fn subscribe(&self) -> broadcast::Receiver<FMSAlarmEvent> {
+        self.events.subscribe()
+    }
+
     pub fn throw_alarm(
         &self,
         alarm_type: FMSAlarmType,
@@ -87,7 +127,10 @@ impl FMSAlarmHandler {
         };
 
         let mut raw = self.raw.write().unwrap();
-        raw.active_alarms.push(new_alarm);
+        raw.active_alarms.push(new_alarm.clone());
+        drop(raw);
+
+        self.notify(FMSAlarmEventType::Thrown, new_alarm);
 
         Ok(())
     }
@@ -98,10 +141,11 @@ impl FMSAlarmHandler {
         for active_alarm in raw.active_alarms.iter_mut() {
             if active_alarm.code == code {
                 active_alarm.released = true;
-                if active_alarm.auto_clear {
-                    let code = active_alarm.code.clone();
-                    drop(raw);
-                    let _ = self.clear_alarm(code.as_str());
+                let alarm = active_alarm.clone();
+                drop(raw);
+                self.notify(FMSAlarmEventType::Released, alarm.clone());
+                if alarm.auto_clear {
+                    let _ = self.clear_alarm(alarm.code.as_str());
                 }
                 return Ok(());
             }
@@ -122,7 +166,10 @@ impl FMSAlarmHandler {
             return Ok(false);
         }
         let alarm = raw.active_alarms.remove(idx);
-        raw.historic_alarms.push(alarm);
+        raw.historic_alarms.push(alarm.clone());
+        drop(raw);
+
+        self.notify(FMSAlarmEventType::Cleared, alarm);
 
         Ok(true)
     }
@@ -161,8 +208,30 @@ impl FMSAlarmHandler {
             active_alarms: Vec::new(),
             historic_alarms: Vec::new(),
         };
+        let (events, _) = broadcast::channel(256);
         Self {
             raw: Arc::new(RwLock::new(alarm_handler)),
+            events,
         }
     }
+
+    /// Puts alarms that were still active when the FMS last stopped back into the active list.
+    /// No events are sent, since the alarms are already recorded.
+    pub(super) fn restore_alarms(&self, alarms: Vec<FMSAlarm>) {
+        let mut raw = self.raw.write().unwrap();
+        for alarm in alarms {
+            if !raw
+                .active_alarms
+                .iter()
+                .any(|active_alarm| active_alarm.code == alarm.code)
+            {
+                raw.active_alarms.push(alarm);
+            }
+        }
+    }
+
+    fn notify(&self, event_type: FMSAlarmEventType, alarm: FMSAlarm) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(FMSAlarmEvent { event_type, alarm });
+    }
 }
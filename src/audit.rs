@@ -0,0 +1,52 @@
+use log::*;
+use serde_json::Value;
+
+use crate::database::{AuditEntry, AuditFilter, Database};
+
+/// The actor recorded for actions the FMS takes on its own, such as advancing the match
+pub const SYSTEM_ACTOR: &str = "fms";
+
+/// Records who changed what, so that disputes can be reconstructed after the fact.
+#[derive(Clone)]
+pub struct AuditLog {
+    database: Database,
+}
+
+impl AuditLog {
+    // Public API -->
+
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Records an operation that has already taken effect. A failure to write the entry is
+    /// logged rather than returned, since the change itself cannot be undone anymore.
+    pub fn record(
+        &self,
+        actor: &str,
+        operation: &str,
+        arguments: Value,
+        previous_value: Option<Value>,
+        new_value: Option<Value>,
+    ) {
+        let entry = AuditEntry {
+            id: 0,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            actor: actor.to_string(),
+            operation: operation.to_string(),
+            arguments: arguments.to_string(),
+            previous_value: previous_value.map(|value| value.to_string()),
+            new_value: new_value.map(|value| value.to_string()),
+        };
+        if let Err(e) = self.database.insert_audit_entry(&entry) {
+            error!(
+                "Could not write audit log entry for {} by {}: {}",
+                operation, actor, e
+            );
+        }
+    }
+
+    pub fn entries(&self, filter: &AuditFilter) -> anyhow::Result<Vec<AuditEntry>> {
+        self.database.audit_entries(filter)
+    }
+}
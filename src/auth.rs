@@ -0,0 +1,575 @@
+pub mod oidc;
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use argon2::{
+    Argon2,
+    password_hash::{
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::{OsRng, RngCore},
+    },
+};
+use log::*;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::database::{ApiKeyRecord, Database, UserRecord};
+
+use self::oidc::OidcConfig;
+
+const BOOTSTRAP_USERNAME: &str = "admin";
+const API_KEY_PREFIX: &str = "nvm_";
+/// How stale the last used time of an API key may get before it is written again
+const API_KEY_LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+/// The `[auth]` table of the config file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// How long a token from `signIn` stays valid
+    pub session_lifetime_secs: u64,
+    /// Enables signing in through an OpenID Connect provider
+    pub oidc: Option<OidcConfig>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            session_lifetime_secs: 12 * 60 * 60,
+            oidc: None,
+        }
+    }
+}
+
+/// What a user is allowed to do through the API.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Scope {
+    /// Read the field, driver stations and alarms. Every other scope includes this one.
+    Readonly,
+    /// Run matches, change field settings and enable or stop driver stations
+    FieldControl,
+    /// Start a match while the prestart check is failing
+    FieldOverride,
+    AlarmsClear,
+    /// Assign teams to alliance stations
+    DsAssign,
+    ScheduleEdit,
+    /// Create users and API keys and change their scopes
+    UsersManage,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 7] = [
+        Scope::Readonly,
+        Scope::FieldControl,
+        Scope::FieldOverride,
+        Scope::AlarmsClear,
+        Scope::DsAssign,
+        Scope::ScheduleEdit,
+        Scope::UsersManage,
+    ];
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scope::Readonly => write!(f, "readonly"),
+            Scope::FieldControl => write!(f, "field.control"),
+            Scope::FieldOverride => write!(f, "field.override"),
+            Scope::AlarmsClear => write!(f, "alarms.clear"),
+            Scope::DsAssign => write!(f, "ds.assign"),
+            Scope::ScheduleEdit => write!(f, "schedule.edit"),
+            Scope::UsersManage => write!(f, "users.manage"),
+        }
+    }
+}
+
+impl TryFrom<String> for Scope {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.to_string() == s)
+            .ok_or_else(|| anyhow!("Unknown scope {}", s))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+impl User {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        scopes_include(&self.scopes, scope)
+    }
+}
+
+/// A long-lived key for a machine integration, such as a scoring tablet or stream overlay.
+#[derive(Clone, Debug)]
+pub struct ApiKey {
+    pub id: i64,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_by: String,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+/// A freshly created API key. The value is only ever available here.
+#[derive(Clone, Debug)]
+pub struct NewApiKey {
+    pub api_key: ApiKey,
+    pub value: String,
+}
+
+/// Whoever is behind an authenticated request
+#[derive(Clone, Debug)]
+pub enum Principal {
+    User(User),
+    ApiKey(ApiKey),
+}
+
+/// An authenticated request. Placed into the GraphQL context by the web server.
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub principal: Principal,
+    /// `None` for API keys, which stay valid until they are revoked
+    pub expires_at: Option<i64>,
+    token_hash: String,
+}
+
+impl Session {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        match &self.principal {
+            Principal::User(user) => user.has_scope(scope),
+            Principal::ApiKey(api_key) => scopes_include(&api_key.scopes, scope),
+        }
+    }
+
+    /// The signed in user, or `None` for API keys
+    pub fn user(&self) -> Option<&User> {
+        match &self.principal {
+            Principal::User(user) => Some(user),
+            Principal::ApiKey(_) => None,
+        }
+    }
+
+    /// The name recorded against actions taken in this session
+    pub fn actor_name(&self) -> String {
+        match &self.principal {
+            Principal::User(user) => user.username.clone(),
+            Principal::ApiKey(api_key) => format!("api-key:{}", api_key.name),
+        }
+    }
+}
+
+/// A bearer token handed out by `Auth::sign_in`. Only its hash is stored, so the value
+/// cannot be recovered later.
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub value: String,
+    pub expires_at: i64,
+    pub scopes: Vec<Scope>,
+}
+
+/// Signs users in and out and turns bearer tokens back into sessions.
+#[derive(Clone)]
+pub struct Auth {
+    database: Database,
+    config: AuthConfig,
+}
+
+impl Auth {
+    // Public API -->
+
+    pub fn new(database: Database, config: AuthConfig) -> Self {
+        Self { database, config }
+    }
+
+    pub fn users(&self) -> anyhow::Result<Vec<User>> {
+        self.database
+            .users()?
+            .into_iter()
+            .map(|record| self.user_from_record(record))
+            .collect()
+    }
+
+    pub fn create_user(
+        &self,
+        username: &str,
+        name: &str,
+        password: &str,
+        scopes: &[Scope],
+    ) -> anyhow::Result<User> {
+        if username.is_empty() {
+            bail!("Username cannot be empty");
+        }
+        if password.len() < 8 {
+            bail!("Password must be at least 8 characters long");
+        }
+        if self.database.user_by_username(username)?.is_some() {
+            bail!("User {} already exists", username);
+        }
+
+        let password_hash = hash_password(password)?;
+        let id = self
+            .database
+            .insert_user(username, name, Some(&password_hash))?;
+        self.database.set_user_scopes(id, &scope_names(scopes))?;
+        info!("Created user {}", username);
+
+        Ok(User {
+            id,
+            username: username.to_string(),
+            name: name.to_string(),
+            scopes: scopes.to_vec(),
+        })
+    }
+
+    /// Replaces the scopes of a user. Sessions that are already signed in pick up the change on
+    /// their next request.
+    pub fn set_user_scopes(&self, username: &str, scopes: &[Scope]) -> anyhow::Result<User> {
+        let Some(record) = self.database.user_by_username(username)? else {
+            bail!("No user named {} exists", username);
+        };
+        self.database
+            .set_user_scopes(record.id, &scope_names(scopes))?;
+        info!(
+            "Scopes of user {} set to {}",
+            username,
+            scope_names(scopes).join(", ")
+        );
+        self.user_from_record(record)
+    }
+
+    /// Checks a username and password and starts a new session. Hashing is deliberately slow,
+    /// so call this off the async runtime.
+    pub fn sign_in(&self, username: &str, password: &str) -> anyhow::Result<Token> {
+        let user = self.database.user_by_username(username)?;
+        let verified = match user.as_ref().and_then(|user| user.password_hash.as_deref()) {
+            Some(password_hash) => verify_password(password, password_hash)?,
+            None => false,
+        };
+        let Some(user) = user.filter(|_| verified) else {
+            warn!("Failed sign in for {}", username);
+            bail!("Invalid username or password");
+        };
+
+        let user = self.user_from_record(user)?;
+        self.start_session(user)
+    }
+
+    /// Signs in a user vouched for by an OpenID Connect provider, creating the user on their
+    /// first sign in. The name and scopes are refreshed from the provider every time.
+    pub fn sign_in_oidc(
+        &self,
+        oidc_subject: &str,
+        username: &str,
+        name: &str,
+        scopes: &[Scope],
+    ) -> anyhow::Result<Token> {
+        let (id, username) = match self.database.user_by_oidc_subject(oidc_subject)? {
+            Some(record) => {
+                self.database.set_user_name(record.id, name)?;
+                (record.id, record.username)
+            }
+            None => {
+                if self.database.user_by_username(username)?.is_some() {
+                    bail!(
+                        "User {} already exists and is not linked to this provider",
+                        username
+                    );
+                }
+                let id = self
+                    .database
+                    .insert_oidc_user(username, name, oidc_subject)?;
+                info!("Created user {} for {}", username, oidc_subject);
+                (id, username.to_string())
+            }
+        };
+        self.database.set_user_scopes(id, &scope_names(scopes))?;
+
+        let user = User {
+            id,
+            username,
+            name: name.to_string(),
+            scopes: scopes.to_vec(),
+        };
+        self.start_session(user)
+    }
+
+    pub fn sign_out(&self, session: &Session) -> anyhow::Result<()> {
+        let Principal::User(user) = &session.principal else {
+            bail!("API keys cannot sign out, revoke them instead");
+        };
+        self.database.delete_session(&session.token_hash)?;
+        info!("User {} signed out", user.username);
+        Ok(())
+    }
+
+    /// Returns the session a bearer token belongs to, or `None` if the token is unknown or
+    /// has expired.
+    pub fn authenticate(&self, token: &str) -> anyhow::Result<Option<Session>> {
+        self.user_session(hash_token(token))
+    }
+
+    /// Looks a session up again, returning `None` once it has been signed out, has expired or
+    /// its API key has been revoked. Picks up changes to its scopes too.
+    pub fn refresh_session(&self, session: &Session) -> anyhow::Result<Option<Session>> {
+        match session.principal {
+            Principal::User(_) => self.user_session(session.token_hash.clone()),
+            Principal::ApiKey(_) => {
+                let Some(record) = self.database.api_key_by_hash(&session.token_hash)? else {
+                    return Ok(None);
+                };
+                Ok(Some(Session {
+                    principal: Principal::ApiKey(self.api_key_from_record(record)?),
+                    expires_at: None,
+                    token_hash: session.token_hash.clone(),
+                }))
+            }
+        }
+    }
+
+    pub fn api_keys(&self) -> anyhow::Result<Vec<ApiKey>> {
+        self.database
+            .api_keys()?
+            .into_iter()
+            .map(|record| self.api_key_from_record(record))
+            .collect()
+    }
+
+    pub fn create_api_key(
+        &self,
+        name: &str,
+        scopes: &[Scope],
+        created_by: &str,
+    ) -> anyhow::Result<NewApiKey> {
+        if name.is_empty() {
+            bail!("API key name cannot be empty");
+        }
+        if scopes.is_empty() {
+            bail!("API key needs at least one scope");
+        }
+
+        let mut key_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut key_bytes);
+        let value = format!("{}{}", API_KEY_PREFIX, hex::encode(key_bytes));
+
+        let now = chrono::Utc::now().timestamp();
+        let id = self.database.insert_api_key(
+            name,
+            &hash_token(&value),
+            created_by,
+            now,
+            &scope_names(scopes),
+        )?;
+        info!(
+            "API key {} ({}) created by {} with scopes {}",
+            id,
+            name,
+            created_by,
+            scope_names(scopes).join(", ")
+        );
+
+        Ok(NewApiKey {
+            api_key: ApiKey {
+                id,
+                name: name.to_string(),
+                scopes: scopes.to_vec(),
+                created_by: created_by.to_string(),
+                created_at: now,
+                last_used_at: None,
+                revoked_at: None,
+            },
+            value,
+        })
+    }
+
+    /// Revokes an API key. Requests already in flight with it still finish.
+    pub fn revoke_api_key(&self, id: i64, revoked_by: &str) -> anyhow::Result<ApiKey> {
+        let now = chrono::Utc::now().timestamp();
+        if !self.database.revoke_api_key(id, now)? {
+            bail!("No active API key with id {} exists", id);
+        }
+        let Some(record) = self.database.api_key(id)? else {
+            bail!("No active API key with id {} exists", id);
+        };
+        info!("API key {} ({}) revoked by {}", id, record.name, revoked_by);
+        self.api_key_from_record(record)
+    }
+
+    /// Returns the session for an API key, or `None` if the key is unknown or revoked. Also
+    /// records that the key was used.
+    pub fn authenticate_api_key(&self, key: &str) -> anyhow::Result<Option<Session>> {
+        let key_hash = hash_token(key);
+        let Some(record) = self.database.api_key_by_hash(&key_hash)? else {
+            return Ok(None);
+        };
+        let now = chrono::Utc::now().timestamp();
+        self.database
+            .touch_api_key(record.id, now, API_KEY_LAST_USED_RESOLUTION_SECS)?;
+        Ok(Some(Session {
+            principal: Principal::ApiKey(self.api_key_from_record(record)?),
+            expires_at: None,
+            token_hash: key_hash,
+        }))
+    }
+
+    /// Creates the first user if nobody can sign in yet. Without a password given, a random
+    /// one is generated and logged once.
+    pub fn bootstrap(&self, password: Option<&str>) -> anyhow::Result<()> {
+        if !self.database.users()?.is_empty() {
+            return Ok(());
+        }
+
+        match password {
+            Some(password) => {
+                self.create_user(BOOTSTRAP_USERNAME, "Administrator", password, &Scope::ALL)?;
+            }
+            None => {
+                let mut password_bytes = [0u8; 12];
+                OsRng.fill_bytes(&mut password_bytes);
+                let password = hex::encode(password_bytes);
+                self.create_user(BOOTSTRAP_USERNAME, "Administrator", &password, &Scope::ALL)?;
+                warn!(
+                    "Created user {} with password {}. Change it, or set --admin-password before the first start.",
+                    BOOTSTRAP_USERNAME, password
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes expired sessions from the database
+    pub fn prune_sessions(&self) -> anyhow::Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let deleted = self.database.delete_expired_sessions(now)?;
+        if deleted > 0 {
+            info!("Removed {} expired sessions", deleted);
+        }
+        Ok(())
+    }
+
+    // Internal API -->
+
+    fn start_session(&self, user: User) -> anyhow::Result<Token> {
+        let mut token_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut token_bytes);
+        let value = hex::encode(token_bytes);
+
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = now + self.config.session_lifetime_secs as i64;
+        self.database
+            .insert_session(&hash_token(&value), user.id, now, expires_at)?;
+        info!("User {} signed in", user.username);
+
+        Ok(Token {
+            value,
+            expires_at,
+            scopes: user.scopes,
+        })
+    }
+
+    fn user_session(&self, token_hash: String) -> anyhow::Result<Option<Session>> {
+        let now = chrono::Utc::now().timestamp();
+        let Some((record, expires_at)) = self.database.session_user(&token_hash, now)? else {
+            return Ok(None);
+        };
+        Ok(Some(Session {
+            principal: Principal::User(self.user_from_record(record)?),
+            expires_at: Some(expires_at),
+            token_hash,
+        }))
+    }
+
+    fn user_from_record(&self, record: UserRecord) -> anyhow::Result<User> {
+        let scopes = parse_scopes(
+            &self.database.user_scopes(record.id)?,
+            &format!("user {}", record.username),
+        );
+        Ok(User {
+            id: record.id,
+            username: record.username,
+            name: record.name,
+            scopes,
+        })
+    }
+
+    fn api_key_from_record(&self, record: ApiKeyRecord) -> anyhow::Result<ApiKey> {
+        let scopes = parse_scopes(
+            &self.database.api_key_scopes(record.id)?,
+            &format!("API key {}", record.name),
+        );
+        Ok(ApiKey {
+            id: record.id,
+            name: record.name,
+            scopes,
+            created_by: record.created_by,
+            created_at: record.created_at,
+            last_used_at: record.last_used_at,
+            revoked_at: record.revoked_at,
+        })
+    }
+}
+
+fn scopes_include(scopes: &[Scope], scope: Scope) -> bool {
+    match scope {
+        Scope::Readonly => !scopes.is_empty(),
+        _ => scopes.contains(&scope),
+    }
+}
+
+fn parse_scopes(scopes: &[String], owner: &str) -> Vec<Scope> {
+    scopes
+        .iter()
+        .filter_map(|scope| match scope.parse() {
+            Ok(scope) => Some(scope),
+            Err(e) => {
+                warn!("Ignoring scope of {}: {}", owner, e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn scope_names(scopes: &[Scope]) -> Vec<String> {
+    scopes.iter().map(|scope| scope.to_string()).collect()
+}
+
+fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow!("Could not hash password: {}", e))?;
+    Ok(password_hash.to_string())
+}
+
+fn verify_password(password: &str, password_hash: &str) -> anyhow::Result<bool> {
+    let password_hash = PasswordHash::new(password_hash)
+        .map_err(|e| anyhow!("Stored password hash is invalid: {}", e))?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &password_hash)
+        .is_ok())
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
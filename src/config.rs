@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use anyhow::Context;
+use log::*;
+use serde::Deserialize;
+
+use crate::{auth::AuthConfig, field::timing::MatchTimings};
+
+/// Settings loaded from the TOML file given with `--config`. Everything has a default so the
+/// FMS can start without a config file at all.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub timing: MatchTimings,
+    pub auth: AuthConfig,
+}
+
+impl Config {
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Config> {
+        let mut config = match path {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("Could not read config file {}", path.display()))?;
+                let config: Config = toml::from_str(&contents)
+                    .with_context(|| format!("Could not parse config file {}", path.display()))?;
+                info!("Loaded config from {}", path.display());
+                config
+            }
+            None => Config::default(),
+        };
+
+        config.timing.validate()?;
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,920 @@
+mod migrations;
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use cidr::AnyIpCidr;
+use log::*;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::{
+    alarms::{FMSAlarm, FMSAlarmType},
+    field::{
+        driverstation::{DriverStationLogData, DriverStationLogMessage},
+        enums::{AllianceStation, MatchState, TournamentLevel},
+    },
+};
+
+use self::migrations::MIGRATIONS;
+
+#[derive(Clone, Debug)]
+pub struct EventSettings {
+    pub event_name: String,
+    pub tournament_level: TournamentLevel,
+    pub match_number: u16,
+    pub play_number: u8,
+    /// The timing profile chosen for each tournament level
+    pub timing_profiles: Vec<(TournamentLevel, String)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DriverStationAssignment {
+    pub team_number: u16,
+    pub alliance_station: AllianceStation,
+    pub expected_ip: Option<AnyIpCidr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchRecord {
+    pub id: i64,
+    pub event_name: String,
+    pub tournament_level: TournamentLevel,
+    pub match_number: u16,
+    pub play_number: u8,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub final_state: Option<String>,
+    pub committed: Option<bool>,
+}
+
+/// A user as stored in the database. `password_hash` is a PHC string, and is `None` for users
+/// that cannot sign in with a password.
+#[derive(Clone, Debug)]
+pub struct UserRecord {
+    pub id: i64,
+    pub username: String,
+    pub name: String,
+    pub password_hash: Option<String>,
+}
+
+/// An API key as stored in the database. Only the hash of the key itself is kept.
+#[derive(Clone, Debug)]
+pub struct ApiKeyRecord {
+    pub id: i64,
+    pub name: String,
+    /// Username of whoever created the key
+    pub created_by: String,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+/// A row of driver station telemetry waiting to be written by `Database::insert_telemetry`
+#[derive(Clone, Debug)]
+pub enum TelemetryRecord {
+    LogData {
+        team_number: u16,
+        match_id: Option<i64>,
+        log_data: DriverStationLogData,
+    },
+    LogMessage {
+        team_number: u16,
+        match_id: Option<i64>,
+        log_message: DriverStationLogMessage,
+    },
+}
+
+/// Narrows down which telemetry rows are read. Timestamps are unix seconds and inclusive.
+#[derive(Clone, Debug, Default)]
+pub struct TelemetryFilter {
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub match_id: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+/// One entry of the audit log. Arguments and values are JSON.
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: u64,
+    pub actor: String,
+    pub operation: String,
+    pub arguments: String,
+    pub previous_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+/// Narrows down which audit log entries are read. Timestamps are unix seconds and inclusive.
+#[derive(Clone, Debug, Default)]
+pub struct AuditFilter {
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub actor: Option<String>,
+    pub operation: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// A handle to the SQLite database holding everything that has to survive an FMS restart.
+#[derive(Clone)]
+pub struct Database {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Database {
+    // Public API -->
+
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Could not open database {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        let database = Self {
+            conn: Arc::new(Mutex::new(conn)),
+        };
+        database.migrate()?;
+
+        info!("Opened database {}", path.display());
+
+        Ok(database)
+    }
+
+    /// Makes sure the database file can still be read
+    pub fn check(&self) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("PRAGMA schema_version", [], |row| row.get::<_, i64>(0))?;
+        Ok(())
+    }
+
+    pub fn event_settings(&self) -> anyhow::Result<Option<EventSettings>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "SELECT tournament_level, profile_name FROM event_timing_profiles
+            ORDER BY tournament_level",
+        )?;
+        let timing_profiles = statement
+            .query_map([], |row| {
+                Ok((TournamentLevel::from_byte(row.get(0)?), row.get(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let event_settings = conn
+            .query_row(
+                "SELECT event_name, tournament_level, match_number, play_number
+                FROM event_settings WHERE id = 1",
+                [],
+                |row| {
+                    Ok(EventSettings {
+                        event_name: row.get(0)?,
+                        tournament_level: TournamentLevel::from_byte(row.get(1)?),
+                        match_number: row.get(2)?,
+                        play_number: row.get(3)?,
+                        timing_profiles,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(event_settings)
+    }
+
+    pub fn save_event_settings(&self, event_settings: &EventSettings) -> anyhow::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let transaction = conn.transaction()?;
+        transaction.execute(
+            "INSERT INTO event_settings (id, event_name, tournament_level, match_number, play_number)
+            VALUES (1, ?1, ?2, ?3, ?4)
+            ON CONFLICT (id) DO UPDATE SET
+                event_name = excluded.event_name,
+                tournament_level = excluded.tournament_level,
+                match_number = excluded.match_number,
+                play_number = excluded.play_number",
+            params![
+                event_settings.event_name,
+                event_settings.tournament_level.to_byte(),
+                event_settings.match_number,
+                event_settings.play_number,
+            ],
+        )?;
+        {
+            let mut save_timing_profile = transaction.prepare_cached(
+                "INSERT INTO event_timing_profiles (tournament_level, profile_name)
+                VALUES (?1, ?2)
+                ON CONFLICT (tournament_level) DO UPDATE SET
+                    profile_name = excluded.profile_name",
+            )?;
+            for (tournament_level, profile_name) in event_settings.timing_profiles.iter() {
+                save_timing_profile.execute(params![tournament_level.to_byte(), profile_name])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn driverstation_assignments(&self) -> anyhow::Result<Vec<DriverStationAssignment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT team_number, alliance_station, expected_ip FROM driverstation_assignments",
+        )?;
+        let assignments = statement
+            .query_map([], |row| {
+                let expected_ip: Option<String> = row.get(2)?;
+                Ok(DriverStationAssignment {
+                    team_number: row.get(0)?,
+                    alliance_station: AllianceStation::from_byte(row.get(1)?),
+                    expected_ip: expected_ip.and_then(|expected_ip| expected_ip.parse().ok()),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(assignments)
+    }
+
+    pub fn save_driverstation_assignment(
+        &self,
+        assignment: &DriverStationAssignment,
+    ) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO driverstation_assignments (team_number, alliance_station, expected_ip)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (team_number) DO UPDATE SET
+                alliance_station = excluded.alliance_station,
+                expected_ip = excluded.expected_ip",
+            params![
+                assignment.team_number,
+                assignment.alliance_station.to_byte(),
+                assignment
+                    .expected_ip
+                    .map(|expected_ip| expected_ip.to_string()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_driverstation_assignment(&self, team_number: u16) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM driverstation_assignments WHERE team_number = ?1",
+            params![team_number],
+        )?;
+        Ok(())
+    }
+
+    /// Inserts or updates an alarm. `cleared` marks alarms that have moved into the history.
+    pub fn save_alarm(&self, alarm: &FMSAlarm, cleared: bool) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO alarms (id, alarm_type, code, description, source_id, target_scope,
+                timestamp, released, auto_clear, cleared)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT (id) DO UPDATE SET
+                released = excluded.released,
+                cleared = excluded.cleared",
+            params![
+                alarm.id,
+                alarm.alarm_type.to_byte(),
+                alarm.code,
+                alarm.description,
+                alarm.source_id,
+                alarm.target_scope,
+                alarm.timestamp,
+                alarm.released,
+                alarm.auto_clear,
+                cleared,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Returns every alarm that has not been cleared yet
+    pub fn uncleared_alarms(&self) -> anyhow::Result<Vec<FMSAlarm>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT id, alarm_type, code, description, source_id, target_scope, timestamp,
+                released, auto_clear
+            FROM alarms WHERE cleared = 0 ORDER BY timestamp",
+        )?;
+        let alarms = statement
+            .query_map([], |row| {
+                Ok(FMSAlarm {
+                    id: row.get(0)?,
+                    alarm_type: FMSAlarmType::from_byte(row.get(1)?),
+                    code: row.get(2)?,
+                    description: row.get(3)?,
+                    source_id: row.get(4)?,
+                    target_scope: row.get(5)?,
+                    timestamp: row.get(6)?,
+                    released: row.get(7)?,
+                    auto_clear: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(alarms)
+    }
+
+    /// Records the start of a match and returns the id of the new match record
+    pub fn insert_match_record(
+        &self,
+        event_name: &str,
+        tournament_level: TournamentLevel,
+        match_number: u16,
+        play_number: u8,
+        started_at: i64,
+    ) -> anyhow::Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO matches (event_name, tournament_level, match_number, play_number,
+                started_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                event_name,
+                tournament_level.to_byte(),
+                match_number,
+                play_number,
+                started_at,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn finish_match_record(
+        &self,
+        id: i64,
+        ended_at: i64,
+        final_state: MatchState,
+    ) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE matches SET ended_at = ?2, final_state = ?3 WHERE id = ?1",
+            params![id, ended_at, final_state.to_string()],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_match_record_committed(&self, id: i64, committed: bool) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE matches SET committed = ?2 WHERE id = ?1",
+            params![id, committed],
+        )?;
+        Ok(())
+    }
+
+    pub fn match_records(&self) -> anyhow::Result<Vec<MatchRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT id, event_name, tournament_level, match_number, play_number, started_at,
+                ended_at, final_state, committed
+            FROM matches ORDER BY id",
+        )?;
+        let match_records = statement
+            .query_map([], match_record_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(match_records)
+    }
+
+    /// Match records that were started but never finished, which only happens when the FMS
+    /// stopped in the middle of a match
+    pub fn unfinished_match_records(&self) -> anyhow::Result<Vec<MatchRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT id, event_name, tournament_level, match_number, play_number, started_at,
+                ended_at, final_state, committed
+            FROM matches WHERE ended_at IS NULL ORDER BY id",
+        )?;
+        let match_records = statement
+            .query_map([], match_record_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(match_records)
+    }
+
+    /// Writes a batch of telemetry rows in a single transaction
+    pub fn insert_telemetry(&self, records: &[TelemetryRecord]) -> anyhow::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let transaction = conn.transaction()?;
+        {
+            let mut insert_log_data = transaction.prepare_cached(
+                "INSERT INTO ds_log_data (team_number, match_id, timestamp, trip_time,
+                    lost_packets, voltage, brownout, watchdog, ds_teleop, ds_auto, ds_disable,
+                    robot_teleop, robot_auto, robot_disable, can_utilization, signal, bandwidth)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                    ?17)",
+            )?;
+            let mut insert_log_message = transaction.prepare_cached(
+                "INSERT INTO ds_log_messages (team_number, match_id, timestamp, local_timestamp,
+                    message)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+
+            for record in records {
+                match record {
+                    TelemetryRecord::LogData {
+                        team_number,
+                        match_id,
+                        log_data,
+                    } => {
+                        insert_log_data.execute(params![
+                            team_number,
+                            match_id,
+                            log_data.timestamp,
+                            log_data.trip_time,
+                            log_data.lost_packets,
+                            log_data.voltage,
+                            log_data.brownout,
+                            log_data.watchdog,
+                            log_data.ds_teleop,
+                            log_data.ds_auto,
+                            log_data.ds_disable,
+                            log_data.robot_teleop,
+                            log_data.robot_auto,
+                            log_data.robot_disable,
+                            log_data.can_utilization,
+                            log_data.signal,
+                            log_data.bandwidth,
+                        ])?;
+                    }
+                    TelemetryRecord::LogMessage {
+                        team_number,
+                        match_id,
+                        log_message,
+                    } => {
+                        insert_log_message.execute(params![
+                            team_number,
+                            match_id,
+                            log_message.timestamp,
+                            log_message.local_timestamp,
+                            log_message.message,
+                        ])?;
+                    }
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn log_data(
+        &self,
+        team_number: u16,
+        filter: &TelemetryFilter,
+    ) -> anyhow::Result<Vec<DriverStationLogData>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "SELECT timestamp, trip_time, lost_packets, voltage, brownout, watchdog, ds_teleop,
+                ds_auto, ds_disable, robot_teleop, robot_auto, robot_disable, can_utilization,
+                signal, bandwidth
+            FROM ds_log_data
+            WHERE team_number = ?1
+                AND (?2 IS NULL OR timestamp >= ?2)
+                AND (?3 IS NULL OR timestamp <= ?3)
+                AND (?4 IS NULL OR match_id = ?4)
+            ORDER BY timestamp
+            LIMIT coalesce(?5, -1)",
+        )?;
+        let log_data = statement
+            .query_map(
+                params![
+                    team_number,
+                    filter.since,
+                    filter.until,
+                    filter.match_id,
+                    filter.limit
+                ],
+                |row| {
+                    Ok(DriverStationLogData {
+                        timestamp: row.get(0)?,
+                        trip_time: row.get(1)?,
+                        lost_packets: row.get(2)?,
+                        voltage: row.get(3)?,
+                        brownout: row.get(4)?,
+                        watchdog: row.get(5)?,
+                        ds_teleop: row.get(6)?,
+                        ds_auto: row.get(7)?,
+                        ds_disable: row.get(8)?,
+                        robot_teleop: row.get(9)?,
+                        robot_auto: row.get(10)?,
+                        robot_disable: row.get(11)?,
+                        can_utilization: row.get(12)?,
+                        signal: row.get(13)?,
+                        bandwidth: row.get(14)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(log_data)
+    }
+
+    pub fn log_messages(
+        &self,
+        team_number: u16,
+        filter: &TelemetryFilter,
+    ) -> anyhow::Result<Vec<DriverStationLogMessage>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "SELECT timestamp, local_timestamp, message
+            FROM ds_log_messages
+            WHERE team_number = ?1
+                AND (?2 IS NULL OR timestamp >= ?2)
+                AND (?3 IS NULL OR timestamp <= ?3)
+                AND (?4 IS NULL OR match_id = ?4)
+            ORDER BY timestamp
+            LIMIT coalesce(?5, -1)",
+        )?;
+        let log_messages = statement
+            .query_map(
+                params![
+                    team_number,
+                    filter.since,
+                    filter.until,
+                    filter.match_id,
+                    filter.limit
+                ],
+                |row| {
+                    Ok(DriverStationLogMessage {
+                        timestamp: row.get(0)?,
+                        local_timestamp: row.get(1)?,
+                        message: row.get(2)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(log_messages)
+    }
+
+    pub fn users(&self) -> anyhow::Result<Vec<UserRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement =
+            conn.prepare("SELECT id, username, name, password_hash FROM users ORDER BY id")?;
+        let users = statement
+            .query_map([], user_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(users)
+    }
+
+    /// Finds the user linked to an OpenID Connect subject, which is `<issuer> <sub>`
+    pub fn user_by_oidc_subject(&self, oidc_subject: &str) -> anyhow::Result<Option<UserRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let user = conn
+            .query_row(
+                "SELECT id, username, name, password_hash FROM users WHERE oidc_subject = ?1",
+                params![oidc_subject],
+                user_from_row,
+            )
+            .optional()?;
+        Ok(user)
+    }
+
+    /// Creates a user that can only sign in through OpenID Connect and returns its id
+    pub fn insert_oidc_user(
+        &self,
+        username: &str,
+        name: &str,
+        oidc_subject: &str,
+    ) -> anyhow::Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO users (username, name, oidc_subject) VALUES (?1, ?2, ?3)",
+            params![username, name, oidc_subject],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn set_user_name(&self, user_id: i64, name: &str) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE users SET name = ?2 WHERE id = ?1",
+            params![user_id, name],
+        )?;
+        Ok(())
+    }
+
+    pub fn user_by_username(&self, username: &str) -> anyhow::Result<Option<UserRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let user = conn
+            .query_row(
+                "SELECT id, username, name, password_hash FROM users WHERE username = ?1",
+                params![username],
+                user_from_row,
+            )
+            .optional()?;
+        Ok(user)
+    }
+
+    /// Creates a user and returns its id
+    pub fn insert_user(
+        &self,
+        username: &str,
+        name: &str,
+        password_hash: Option<&str>,
+    ) -> anyhow::Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO users (username, name, password_hash) VALUES (?1, ?2, ?3)",
+            params![username, name, password_hash],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn user_scopes(&self, user_id: i64) -> anyhow::Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement =
+            conn.prepare_cached("SELECT scope FROM user_scopes WHERE user_id = ?1 ORDER BY scope")?;
+        let scopes = statement
+            .query_map(params![user_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(scopes)
+    }
+
+    /// Replaces every scope granted to a user
+    pub fn set_user_scopes(&self, user_id: i64, scopes: &[String]) -> anyhow::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let transaction = conn.transaction()?;
+        transaction.execute(
+            "DELETE FROM user_scopes WHERE user_id = ?1",
+            params![user_id],
+        )?;
+        for scope in scopes {
+            transaction.execute(
+                "INSERT INTO user_scopes (user_id, scope) VALUES (?1, ?2)",
+                params![user_id, scope],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn insert_session(
+        &self,
+        token_hash: &str,
+        user_id: i64,
+        created_at: i64,
+        expires_at: i64,
+    ) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sessions (token_hash, user_id, created_at, expires_at)
+            VALUES (?1, ?2, ?3, ?4)",
+            params![token_hash, user_id, created_at, expires_at],
+        )?;
+        Ok(())
+    }
+
+    /// Looks up the user a session belongs to, along with when the session expires. Expired
+    /// sessions are never returned.
+    pub fn session_user(
+        &self,
+        token_hash: &str,
+        now: i64,
+    ) -> anyhow::Result<Option<(UserRecord, i64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "SELECT users.id, users.username, users.name, users.password_hash,
+                sessions.expires_at
+            FROM sessions JOIN users ON users.id = sessions.user_id
+            WHERE sessions.token_hash = ?1 AND sessions.expires_at > ?2",
+        )?;
+        let session_user = statement
+            .query_row(params![token_hash, now], |row| {
+                Ok((user_from_row(row)?, row.get(4)?))
+            })
+            .optional()?;
+        Ok(session_user)
+    }
+
+    pub fn delete_session(&self, token_hash: &str) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM sessions WHERE token_hash = ?1",
+            params![token_hash],
+        )?;
+        Ok(())
+    }
+
+    /// Removes every session that expired before `now` and returns how many were removed
+    pub fn delete_expired_sessions(&self, now: i64) -> anyhow::Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM sessions WHERE expires_at <= ?1", params![now])?;
+        Ok(deleted)
+    }
+
+    pub fn api_keys(&self) -> anyhow::Result<Vec<ApiKeyRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT id, name, created_by, created_at, last_used_at, revoked_at
+            FROM api_keys ORDER BY id",
+        )?;
+        let api_keys = statement
+            .query_map([], api_key_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(api_keys)
+    }
+
+    pub fn api_key(&self, id: i64) -> anyhow::Result<Option<ApiKeyRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let api_key = conn
+            .query_row(
+                "SELECT id, name, created_by, created_at, last_used_at, revoked_at
+                FROM api_keys WHERE id = ?1",
+                params![id],
+                api_key_from_row,
+            )
+            .optional()?;
+        Ok(api_key)
+    }
+
+    /// Looks up the API key with the given hash. Revoked keys are never returned.
+    pub fn api_key_by_hash(&self, key_hash: &str) -> anyhow::Result<Option<ApiKeyRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "SELECT id, name, created_by, created_at, last_used_at, revoked_at
+            FROM api_keys WHERE key_hash = ?1 AND revoked_at IS NULL",
+        )?;
+        let api_key = statement
+            .query_row(params![key_hash], api_key_from_row)
+            .optional()?;
+        Ok(api_key)
+    }
+
+    /// Creates an API key along with its scopes and returns its id
+    pub fn insert_api_key(
+        &self,
+        name: &str,
+        key_hash: &str,
+        created_by: &str,
+        created_at: i64,
+        scopes: &[String],
+    ) -> anyhow::Result<i64> {
+        let mut conn = self.conn.lock().unwrap();
+        let transaction = conn.transaction()?;
+        transaction.execute(
+            "INSERT INTO api_keys (name, key_hash, created_by, created_at)
+            VALUES (?1, ?2, ?3, ?4)",
+            params![name, key_hash, created_by, created_at],
+        )?;
+        let id = transaction.last_insert_rowid();
+        for scope in scopes {
+            transaction.execute(
+                "INSERT INTO api_key_scopes (api_key_id, scope) VALUES (?1, ?2)",
+                params![id, scope],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(id)
+    }
+
+    pub fn api_key_scopes(&self, api_key_id: i64) -> anyhow::Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "SELECT scope FROM api_key_scopes WHERE api_key_id = ?1 ORDER BY scope",
+        )?;
+        let scopes = statement
+            .query_map(params![api_key_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(scopes)
+    }
+
+    /// Returns `false` if the key does not exist or was already revoked
+    pub fn revoke_api_key(&self, id: i64, revoked_at: i64) -> anyhow::Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE api_keys SET revoked_at = ?2 WHERE id = ?1 AND revoked_at IS NULL",
+            params![id, revoked_at],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Records that an API key was used. To keep busy integrations from writing on every
+    /// request, the time is only updated once it is more than `resolution_secs` old.
+    pub fn touch_api_key(&self, id: i64, now: i64, resolution_secs: i64) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "UPDATE api_keys SET last_used_at = ?2
+            WHERE id = ?1 AND (last_used_at IS NULL OR last_used_at <= ?2 - ?3)",
+        )?;
+        statement.execute(params![id, now, resolution_secs])?;
+        Ok(())
+    }
+
+    /// Appends an entry to the audit log. The id of `entry` is ignored.
+    pub fn insert_audit_entry(&self, entry: &AuditEntry) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "INSERT INTO audit_log
+                (timestamp, actor, operation, arguments, previous_value, new_value)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        statement.execute(params![
+            entry.timestamp,
+            entry.actor,
+            entry.operation,
+            entry.arguments,
+            entry.previous_value,
+            entry.new_value
+        ])?;
+        Ok(())
+    }
+
+    /// Reads audit log entries, oldest first
+    pub fn audit_entries(&self, filter: &AuditFilter) -> anyhow::Result<Vec<AuditEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(
+            "SELECT id, timestamp, actor, operation, arguments, previous_value, new_value
+            FROM audit_log
+            WHERE (?1 IS NULL OR timestamp >= ?1)
+                AND (?2 IS NULL OR timestamp <= ?2)
+                AND (?3 IS NULL OR actor = ?3)
+                AND (?4 IS NULL OR operation = ?4)
+            ORDER BY id
+            LIMIT coalesce(?5, -1)",
+        )?;
+        let entries = statement
+            .query_map(
+                params![
+                    filter.since,
+                    filter.until,
+                    filter.actor,
+                    filter.operation,
+                    filter.limit
+                ],
+                |row| {
+                    Ok(AuditEntry {
+                        id: row.get(0)?,
+                        timestamp: row.get(1)?,
+                        actor: row.get(2)?,
+                        operation: row.get(3)?,
+                        arguments: row.get(4)?,
+                        previous_value: row.get(5)?,
+                        new_value: row.get(6)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    // Internal API -->
+
+    /// Brings the schema up to date by applying every migration newer than the database's
+    /// `user_version`, each in its own transaction.
+    fn migrate(&self) -> anyhow::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let current_version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        if current_version > MIGRATIONS.len() {
+            anyhow::bail!(
+                "Database schema version {} is newer than this FMS supports ({})",
+                current_version,
+                MIGRATIONS.len()
+            );
+        }
+
+        for (idx, migration) in MIGRATIONS.iter().enumerate().skip(current_version) {
+            let version = idx + 1;
+            let transaction = conn.transaction()?;
+            transaction
+                .execute_batch(migration)
+                .with_context(|| format!("Database migration {} failed", version))?;
+            transaction.pragma_update(None, "user_version", version)?;
+            transaction.commit()?;
+            info!("Applied database migration {}", version);
+        }
+
+        Ok(())
+    }
+}
+
+fn match_record_from_row(row: &rusqlite::Row) -> rusqlite::Result<MatchRecord> {
+    Ok(MatchRecord {
+        id: row.get(0)?,
+        event_name: row.get(1)?,
+        tournament_level: TournamentLevel::from_byte(row.get(2)?),
+        match_number: row.get(3)?,
+        play_number: row.get(4)?,
+        started_at: row.get(5)?,
+        ended_at: row.get(6)?,
+        final_state: row.get(7)?,
+        committed: row.get(8)?,
+    })
+}
+
+fn user_from_row(row: &rusqlite::Row) -> rusqlite::Result<UserRecord> {
+    Ok(UserRecord {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        name: row.get(2)?,
+        password_hash: row.get(3)?,
+    })
+}
+
+fn api_key_from_row(row: &rusqlite::Row) -> rusqlite::Result<ApiKeyRecord> {
+    Ok(ApiKeyRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_by: row.get(2)?,
+        created_at: row.get(3)?,
+        last_used_at: row.get(4)?,
+        revoked_at: row.get(5)?,
+    })
+}
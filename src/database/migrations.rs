@@ -0,0 +1,164 @@
+/// Every schema migration in the order it must be applied. The index of a migration plus one
+/// is the `user_version` the database has once it has been applied, so existing entries must
+/// never be changed or reordered. Add new migrations to the end of the list.
+pub const MIGRATIONS: &[&str] = &[
+    // 1: Initial schema
+    "
+    CREATE TABLE event_settings (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        event_name TEXT NOT NULL,
+        tournament_level INTEGER NOT NULL,
+        match_number INTEGER NOT NULL,
+        play_number INTEGER NOT NULL
+    );
+
+    CREATE TABLE driverstation_assignments (
+        team_number INTEGER PRIMARY KEY,
+        alliance_station INTEGER NOT NULL UNIQUE,
+        expected_ip TEXT
+    );
+
+    CREATE TABLE alarms (
+        id TEXT PRIMARY KEY,
+        alarm_type INTEGER NOT NULL,
+        code TEXT NOT NULL,
+        description TEXT NOT NULL,
+        source_id TEXT NOT NULL,
+        target_scope TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        released INTEGER NOT NULL,
+        auto_clear INTEGER NOT NULL,
+        cleared INTEGER NOT NULL DEFAULT 0
+    );
+
+    CREATE TABLE matches (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        event_name TEXT NOT NULL,
+        tournament_level INTEGER NOT NULL,
+        match_number INTEGER NOT NULL,
+        play_number INTEGER NOT NULL,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER,
+        final_state TEXT,
+        committed INTEGER
+    );
+
+    CREATE TABLE ds_log_data (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        team_number INTEGER NOT NULL,
+        match_id INTEGER REFERENCES matches (id),
+        timestamp INTEGER NOT NULL,
+        trip_time INTEGER NOT NULL,
+        lost_packets INTEGER NOT NULL,
+        voltage REAL NOT NULL,
+        brownout INTEGER NOT NULL,
+        watchdog INTEGER NOT NULL,
+        ds_teleop INTEGER NOT NULL,
+        ds_auto INTEGER NOT NULL,
+        ds_disable INTEGER NOT NULL,
+        robot_teleop INTEGER NOT NULL,
+        robot_auto INTEGER NOT NULL,
+        robot_disable INTEGER NOT NULL,
+        can_utilization INTEGER NOT NULL,
+        signal INTEGER NOT NULL,
+        bandwidth REAL NOT NULL
+    );
+
+    CREATE INDEX ds_log_data_team_number_timestamp ON ds_log_data (team_number, timestamp);
+
+    CREATE TABLE ds_log_messages (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        team_number INTEGER NOT NULL,
+        match_id INTEGER REFERENCES matches (id),
+        timestamp INTEGER NOT NULL,
+        local_timestamp INTEGER NOT NULL,
+        message TEXT NOT NULL
+    );
+
+    CREATE INDEX ds_log_messages_team_number_timestamp ON ds_log_messages (team_number, timestamp);
+    ",
+    // 2: Users and sessions
+    "
+    CREATE TABLE users (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        username TEXT NOT NULL UNIQUE,
+        name TEXT NOT NULL,
+        password_hash TEXT
+    );
+
+    CREATE TABLE sessions (
+        token_hash TEXT PRIMARY KEY,
+        user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        created_at INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL
+    );
+
+    CREATE INDEX sessions_expires_at ON sessions (expires_at);
+    ",
+    // 3: User scopes
+    "
+    CREATE TABLE user_scopes (
+        user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        scope TEXT NOT NULL,
+        PRIMARY KEY (user_id, scope)
+    );
+
+    -- Users created before scopes existed keep the full access they already had
+    INSERT INTO user_scopes (user_id, scope)
+    SELECT users.id, scopes.scope
+    FROM users, (
+        SELECT 'readonly' AS scope
+        UNION ALL SELECT 'field.control'
+        UNION ALL SELECT 'field.override'
+        UNION ALL SELECT 'alarms.clear'
+        UNION ALL SELECT 'ds.assign'
+        UNION ALL SELECT 'schedule.edit'
+        UNION ALL SELECT 'users.manage'
+    ) AS scopes;
+    ",
+    // 4: Users signed in through OpenID Connect
+    "
+    ALTER TABLE users ADD COLUMN oidc_subject TEXT;
+
+    CREATE UNIQUE INDEX users_oidc_subject ON users (oidc_subject);
+    ",
+    // 5: API keys
+    "
+    CREATE TABLE api_keys (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        key_hash TEXT NOT NULL UNIQUE,
+        created_by TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        last_used_at INTEGER,
+        revoked_at INTEGER
+    );
+
+    CREATE TABLE api_key_scopes (
+        api_key_id INTEGER NOT NULL REFERENCES api_keys (id) ON DELETE CASCADE,
+        scope TEXT NOT NULL,
+        PRIMARY KEY (api_key_id, scope)
+    );
+    ",
+    // 6: Audit log
+    "
+    CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp INTEGER NOT NULL,
+        actor TEXT NOT NULL,
+        operation TEXT NOT NULL,
+        arguments TEXT NOT NULL,
+        previous_value TEXT,
+        new_value TEXT
+    );
+
+    CREATE INDEX audit_log_timestamp ON audit_log (timestamp);
+    ",
+    // 7: Timing profile chosen for each tournament level
+    "
+    CREATE TABLE event_timing_profiles (
+        tournament_level INTEGER PRIMARY KEY,
+        profile_name TEXT NOT NULL
+    );
+    ",
+];
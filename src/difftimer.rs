@@ -33,6 +33,15 @@ impl DiffTimer {
         self.started_at.is_some()
     }
 
+    pub fn started_at(&self) -> Option<Instant> {
+        self.started_at
+    }
+
+    /// The time remaining at `started_at`, or the frozen time if the timer is stopped
+    pub fn time_remaining(&self) -> Duration {
+        self.time_remaining
+    }
+
     pub fn current_time_remaining(&self) -> Duration {
         if self.is_running() {
             let time_passed = self.started_at.unwrap().elapsed();
@@ -1,24 +1,48 @@
 pub mod connection;
 pub mod driverstation;
 pub mod enums;
+pub mod gamedata;
+pub mod packetstats;
+pub mod prestart;
+pub mod protocol;
+pub mod tags;
+pub mod timing;
 
 use std::{
+    collections::HashMap,
     net::{IpAddr, SocketAddr},
     sync::{Arc, RwLock},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use anyhow::Context;
+use anyhow::{Context, bail};
 use log::*;
+use serde_json::json;
 use tokio::{
     net::{TcpListener, UdpSocket},
+    sync::{broadcast, mpsc},
     task::JoinSet,
 };
 use tokio_util::sync::CancellationToken;
 
-use crate::{alarms::FMSAlarmHandler, difftimer};
+use crate::{
+    alarms::{FMSAlarmEvent, FMSAlarmEventType, FMSAlarmHandler, FMSAlarmType},
+    audit::{AuditLog, SYSTEM_ACTOR},
+    database::{Database, EventSettings, TelemetryRecord},
+    difftimer,
+    metrics::Metrics,
+};
+
+use self::{
+    driverstation::DriverStations,
+    enums::{AllianceStation, MatchState, TournamentLevel},
+    gamedata::GameData,
+    prestart::PreStartCheck,
+    protocol::ProtocolError,
+    timing::{MatchTimingProfile, MatchTimings},
+};
 
-use self::{driverstation::DriverStations, enums::TournamentLevel};
+const TELEMETRY_BATCH_SIZE: usize = 512;
 
 struct RawField {
     event_name: String,
@@ -26,17 +50,38 @@ struct RawField {
     match_number: u16,
     play_number: u8,
     time_left: difftimer::DiffTimer,
+    match_state: MatchState,
+    match_paused: bool,
+    post_match_safe_at: Option<Instant>,
+    timings: MatchTimings,
+    game_data: HashMap<AllianceStation, GameData>,
     ds_mode: enums::Mode,
     is_safe: bool,
     udp_online: bool,
     tcp_online: bool,
+    last_tick_at: Option<Instant>,
     driverstations: DriverStations,
     alarm_handler: FMSAlarmHandler,
+    audit_log: AuditLog,
+    metrics: Metrics,
+    database: Database,
+    current_match_id: Option<i64>,
+    telemetry_receiver: Option<mpsc::Receiver<TelemetryRecord>>,
+    alarm_receiver: Option<broadcast::Receiver<FMSAlarmEvent>>,
+}
+
+/// Sent to every subscriber of `Field::subscribe` whenever something observable changes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FieldUpdate {
+    FieldState,
+    DriverStation { team_number: u16 },
 }
 
 #[derive(Clone)]
 pub struct Field {
     raw: Arc<RwLock<RawField>>,
+    updates: broadcast::Sender<FieldUpdate>,
+    telemetry: mpsc::Sender<TelemetryRecord>,
 }
 
 impl Field {
@@ -51,6 +96,17 @@ impl Field {
         raw.tcp_online
     }
 
+    /// When the tick loop last finished a tick
+    pub fn last_tick_at(&self) -> Option<Instant> {
+        let raw = self.raw.read().unwrap();
+        raw.last_tick_at
+    }
+
+    /// Receives a `FieldUpdate` every time the field state or a driver station changes
+    pub fn subscribe(&self) -> broadcast::Receiver<FieldUpdate> {
+        self.updates.subscribe()
+    }
+
     pub fn driverstations(&self) -> DriverStations {
         let raw = self.raw.read().unwrap();
         raw.driverstations.clone()
@@ -61,6 +117,28 @@ impl Field {
         raw.alarm_handler.clone()
     }
 
+    pub fn audit_log(&self) -> AuditLog {
+        let raw = self.raw.read().unwrap();
+        raw.audit_log.clone()
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        let raw = self.raw.read().unwrap();
+        raw.metrics.clone()
+    }
+
+    pub fn database(&self) -> Database {
+        let raw = self.raw.read().unwrap();
+        raw.database.clone()
+    }
+
+    /// The id of the match record for the match currently on the field, from the start of
+    /// autonomous until the match is committed or discarded
+    pub fn current_match_id(&self) -> Option<i64> {
+        let raw = self.raw.read().unwrap();
+        raw.current_match_id
+    }
+
     pub fn alarm_target(&self) -> String {
         "fms.field".to_string()
     }
@@ -70,10 +148,80 @@ impl Field {
         raw.event_name.clone()
     }
 
-    pub fn set_event_name(&self, event_name: String) {
+    /// Sets the event code sent to every driver station. It must fit in the single length
+    /// byte of the event code packet.
+    pub fn set_event_name(&self, event_name: String) -> anyhow::Result<()> {
+        if event_name.is_empty() {
+            bail!("Event name cannot be empty");
+        }
+        if event_name.len() > u8::MAX as usize {
+            bail!(
+                "Event name cannot be longer than {} bytes, got {}",
+                u8::MAX,
+                event_name.len()
+            );
+        }
         let mut raw = self.raw.write().unwrap();
         raw.event_name = event_name;
         info!("Event name set to {}", raw.event_name.clone());
+        drop(raw);
+        self.save_event_settings();
+        self.notify(FieldUpdate::FieldState);
+        Ok(())
+    }
+
+    pub fn game_data(&self) -> HashMap<AllianceStation, GameData> {
+        let raw = self.raw.read().unwrap();
+        raw.game_data.clone()
+    }
+
+    /// Sets or clears the game data of an alliance station. Driver stations are sent the change
+    /// as soon as it is released.
+    pub fn set_game_data(
+        &self,
+        alliance_station: AllianceStation,
+        game_data: Option<GameData>,
+    ) -> anyhow::Result<()> {
+        if alliance_station == AllianceStation::None {
+            bail!("Game data can only be set for one of the six alliance stations");
+        }
+        if let Some(game_data) = game_data.as_ref()
+            && game_data.message.len() > u8::MAX as usize
+        {
+            bail!(
+                "Game data cannot be longer than {} bytes, got {}",
+                u8::MAX,
+                game_data.message.len()
+            );
+        }
+        let mut raw = self.raw.write().unwrap();
+        match game_data {
+            Some(game_data) => {
+                info!(
+                    "Game data for {} set to {:?} from {:?}",
+                    alliance_station, game_data.message, game_data.release
+                );
+                raw.game_data.insert(alliance_station, game_data);
+            }
+            None => {
+                info!("Game data for {} cleared", alliance_station);
+                raw.game_data.remove(&alliance_station);
+            }
+        }
+        drop(raw);
+        self.notify(FieldUpdate::FieldState);
+        Ok(())
+    }
+
+    /// The game data an alliance station should have right now, which is empty until the
+    /// match reaches the period it is released in
+    pub fn released_game_data(&self, alliance_station: AllianceStation) -> String {
+        let raw = self.raw.read().unwrap();
+        raw.game_data
+            .get(&alliance_station)
+            .filter(|game_data| game_data.release.is_released(raw.match_state))
+            .map(|game_data| game_data.message.clone())
+            .unwrap_or_default()
     }
 
     pub fn tournament_level(&self) -> TournamentLevel {
@@ -85,6 +233,9 @@ impl Field {
         let mut raw = self.raw.write().unwrap();
         raw.tournament_level = tournament_level;
         info!("Tournament Level set to {}", raw.tournament_level.clone());
+        drop(raw);
+        self.save_event_settings();
+        self.notify(FieldUpdate::FieldState);
     }
 
     pub fn match_number(&self) -> u16 {
@@ -96,6 +247,9 @@ impl Field {
         let mut raw = self.raw.write().unwrap();
         raw.match_number = match_number;
         info!("Match Number set to {}", &raw.match_number);
+        drop(raw);
+        self.save_event_settings();
+        self.notify(FieldUpdate::FieldState);
     }
 
     pub fn play_number(&self) -> u8 {
@@ -107,6 +261,9 @@ impl Field {
         let mut raw = self.raw.write().unwrap();
         raw.play_number = play_number;
         info!("Play number set to {}", &raw.play_number);
+        drop(raw);
+        self.save_event_settings();
+        self.notify(FieldUpdate::FieldState);
     }
 
     pub fn timer(&self) -> difftimer::DiffTimer {
@@ -118,6 +275,8 @@ impl Field {
         let mut raw = self.raw.write().unwrap();
         raw.time_left = difftimer::DiffTimer::new(time_left, raw.time_left.is_running());
         info!("Timer set to {} ms", time_left.as_millis());
+        drop(raw);
+        self.notify(FieldUpdate::FieldState);
     }
 
     pub fn start_timer(&self) {
@@ -125,6 +284,8 @@ impl Field {
         if !raw.time_left.is_running() {
             raw.time_left = raw.time_left.start();
             info!("Timer started");
+            drop(raw);
+            self.notify(FieldUpdate::FieldState);
         }
     }
 
@@ -133,12 +294,187 @@ impl Field {
         if raw.time_left.is_running() {
             raw.time_left = raw.time_left.stop();
             info!("Timer stopped");
+            drop(raw);
+            self.notify(FieldUpdate::FieldState);
+        }
+    }
+
+    pub fn match_state(&self) -> MatchState {
+        let raw = self.raw.read().unwrap();
+        raw.match_state
+    }
+
+    /// Moves an idle field into `MatchState::PreStart`, readying the driver stations for
+    /// autonomous while keeping every robot disabled.
+    pub fn prestart_match(&self) -> anyhow::Result<()> {
+        let match_state = self.match_state();
+        if match_state != MatchState::Idle {
+            bail!("Cannot prestart a match while the field is in state {match_state}");
         }
+        self.set_match_state(MatchState::PreStart);
+        Ok(())
+    }
+
+    /// Checks that every assigned driver station is connected and talking to its robot, and
+    /// that the field itself is ready for a match to start.
+    pub fn prestart_check(&self) -> PreStartCheck {
+        PreStartCheck::run(self)
+    }
+
+    /// Starts the autonomous period. From here on the tick loop advances the match on its own.
+    ///
+    /// The match will not start while the prestart check is failing unless `override_checks`
+    /// is set. Active field faults and a field still flagged as safe can never be overridden,
+    /// see `PreStartFailure::can_override`.
+    pub fn start_match(&self, override_checks: bool) -> anyhow::Result<()> {
+        let match_state = self.match_state();
+        if match_state != MatchState::Idle && match_state != MatchState::PreStart {
+            bail!("Cannot start a match while the field is in state {match_state}");
+        }
+        let prestart_check = self.prestart_check();
+        if !prestart_check.is_ready() {
+            if !override_checks || !prestart_check.can_override() {
+                bail!("Prestart check failed: {prestart_check}");
+            }
+            warn!("Starting match with failing prestart check: {prestart_check}");
+        }
+        self.set_match_state(MatchState::Auto);
+        Ok(())
     }
 
+    /// Ends a running match early, moving it into `MatchState::Aborted`.
+    pub fn stop_match(&self) -> anyhow::Result<()> {
+        let match_state = self.match_state();
+        if !match_state.is_running() {
+            bail!("Cannot stop a match while the field is in state {match_state}");
+        }
+        self.set_match_state(MatchState::Aborted);
+        Ok(())
+    }
+
+    /// Stops the timer and disables every driver station. Does nothing to the match state
+    /// unless a match is currently running.
     pub fn match_abort(&self) {
-        self.stop_timer()
-        //TODO Other actions related to match abort
+        if self.match_state().is_running() {
+            self.set_match_state(MatchState::Aborted);
+        } else {
+            self.stop_timer();
+        }
+    }
+
+    pub fn match_paused(&self) -> bool {
+        let raw = self.raw.read().unwrap();
+        raw.match_paused
+    }
+
+    pub fn timings(&self) -> MatchTimings {
+        let raw = self.raw.read().unwrap();
+        raw.timings.clone()
+    }
+
+    /// The timing profile used for matches at the current tournament level
+    pub fn timing_profile(&self) -> MatchTimingProfile {
+        let raw = self.raw.read().unwrap();
+        raw.timings.profile(raw.tournament_level)
+    }
+
+    pub fn set_timing_profile(
+        &self,
+        tournament_level: TournamentLevel,
+        name: &str,
+    ) -> anyhow::Result<()> {
+        let mut raw = self.raw.write().unwrap();
+        raw.timings.set_profile_name(tournament_level, name)?;
+        info!("Timing profile for {} set to {}", tournament_level, name);
+        drop(raw);
+        self.save_event_settings();
+        self.notify(FieldUpdate::FieldState);
+        Ok(())
+    }
+
+    /// Returns `true` once teleop has reached the endgame warning point of the current
+    /// timing profile
+    pub fn is_endgame(&self) -> bool {
+        self.match_state() == MatchState::Teleop
+            && self.timer().current_time_remaining() <= self.timing_profile().endgame_warning()
+    }
+
+    /// Freezes the period timer and disables every driver station without leaving the
+    /// current match state.
+    pub fn pause_match(&self) -> anyhow::Result<()> {
+        let match_state = self.match_state();
+        if !match_state.is_running() {
+            bail!("Cannot pause a match while the field is in state {match_state}");
+        }
+        {
+            let mut raw = self.raw.write().unwrap();
+            if raw.match_paused {
+                bail!("Match is already paused");
+            }
+            raw.match_paused = true;
+            raw.time_left = raw.time_left.stop();
+        }
+        for ds in self.driverstations().get_all_driverstations() {
+            ds.set_commanded_enabled(false);
+        }
+        info!("Match paused during {match_state}");
+        self.notify(FieldUpdate::FieldState);
+        Ok(())
+    }
+
+    pub fn unpause_match(&self) -> anyhow::Result<()> {
+        let match_state = self.match_state();
+        if !match_state.is_running() {
+            bail!("Cannot unpause a match while the field is in state {match_state}");
+        }
+        if self
+            .alarm_handler()
+            .is_target_faulted(self.alarm_target().as_str())
+        {
+            bail!("Cannot unpause a match while the field has an active fault");
+        }
+        {
+            let mut raw = self.raw.write().unwrap();
+            if !raw.match_paused {
+                bail!("Match is not paused");
+            }
+            raw.match_paused = false;
+            raw.time_left = raw.time_left.start();
+        }
+        let enabled = match_state.is_enabled_period();
+        for ds in self.driverstations().get_all_driverstations() {
+            ds.set_commanded_enabled(enabled && !ds.manually_disabled());
+        }
+        info!("Match unpaused during {match_state}");
+        self.notify(FieldUpdate::FieldState);
+        Ok(())
+    }
+
+    /// Accepts the result of a finished or aborted match and readies the field for the next
+    /// match number.
+    pub fn commit_match(&self) -> anyhow::Result<()> {
+        let match_state = self.match_state();
+        if match_state != MatchState::PostMatch && match_state != MatchState::Aborted {
+            bail!("Cannot commit a match while the field is in state {match_state}");
+        }
+        self.record_match_result(true);
+        self.set_match_state(MatchState::Idle);
+        self.set_match_number(self.match_number().wrapping_add(1));
+        self.set_play_number(1);
+        Ok(())
+    }
+
+    /// Throws away the result of a finished or aborted match so that it can be replayed under
+    /// the next play number.
+    pub fn discard_match(&self) -> anyhow::Result<()> {
+        let match_state = self.match_state();
+        if match_state != MatchState::PostMatch && match_state != MatchState::Aborted {
+            bail!("Cannot discard a match while the field is in state {match_state}");
+        }
+        self.record_match_result(false);
+        self.set_match_state(MatchState::Idle);
+        self.set_play_number(self.play_number().wrapping_add(1));
+        Ok(())
     }
 
     pub fn ds_mode(&self) -> enums::Mode {
@@ -150,6 +486,8 @@ impl Field {
         let mut raw = self.raw.write().unwrap();
         raw.ds_mode = ds_mode;
         info!("DS Mode set to {ds_mode}");
+        drop(raw);
+        self.notify(FieldUpdate::FieldState);
     }
 
     pub fn is_safe(&self) -> bool {
@@ -157,36 +495,69 @@ impl Field {
         raw.is_safe
     }
 
-    pub fn set_is_safe(&self, is_safe: bool) {
+    /// Flags the field as safe to enter. Refused while a match is running, as robots would
+    /// stay enabled on a field marked safe.
+    pub fn set_is_safe(&self, is_safe: bool) -> anyhow::Result<()> {
         let mut raw = self.raw.write().unwrap();
+        if is_safe && raw.match_state.is_running() {
+            bail!(
+                "Cannot mark the field safe while the field is in state {}",
+                raw.match_state
+            );
+        }
         raw.is_safe = is_safe;
         info!("Field safe flag set to {}", is_safe);
+        drop(raw);
+        self.notify(FieldUpdate::FieldState);
+        Ok(())
     }
 
     // Internal API -->
 
-    pub(super) fn new() -> Self {
+    pub(super) fn new(timings: MatchTimings, database: Database) -> anyhow::Result<Self> {
+        let (updates, _) = broadcast::channel(256);
+        let (telemetry, telemetry_receiver) = mpsc::channel(TELEMETRY_BATCH_SIZE * 8);
+        let alarm_handler = FMSAlarmHandler::new();
+        // Subscribe before restoring so the alarm recorder also sees the recovery alarm
+        let alarm_receiver = alarm_handler.subscribe();
+
         let field = RawField {
             event_name: "nvmre".to_string(),
             tournament_level: TournamentLevel::Test,
             match_number: 1,
             play_number: 1,
             time_left: difftimer::DiffTimer::new(Duration::ZERO, false),
+            match_state: MatchState::Idle,
+            match_paused: false,
+            post_match_safe_at: None,
+            timings,
+            game_data: HashMap::new(),
             ds_mode: enums::Mode::Autonomous,
             driverstations: DriverStations::new(None),
-            alarm_handler: FMSAlarmHandler::new(),
+            alarm_handler,
+            audit_log: AuditLog::new(database.clone()),
+            metrics: Metrics::new()?,
+            database,
+            current_match_id: None,
+            telemetry_receiver: Some(telemetry_receiver),
+            alarm_receiver: Some(alarm_receiver),
             is_safe: true,
             udp_online: false,
             tcp_online: false,
+            last_tick_at: None,
         };
 
         let field = Self {
             raw: Arc::new(RwLock::new(field)),
+            updates,
+            telemetry,
         };
 
         field.driverstations().set_field(field.clone()).unwrap();
 
-        field
+        field.restore()?;
+
+        Ok(field)
     }
 
     pub(super) async fn run(
@@ -216,6 +587,28 @@ impl Field {
             .name("Field tick loop")
             .spawn(self.clone().tick_loop(cancellation_token.clone()))?;
 
+        let alarm_receiver = {
+            let mut raw = self.raw.write().unwrap();
+            raw.alarm_receiver
+                .take()
+                .context("Field alarm recorder already started")?
+        };
+        tasks.build_task().name("Field alarm recorder").spawn(
+            self.clone()
+                .record_alarms(alarm_receiver, cancellation_token.clone()),
+        )?;
+
+        let telemetry_receiver = {
+            let mut raw = self.raw.write().unwrap();
+            raw.telemetry_receiver
+                .take()
+                .context("Field telemetry writer already started")?
+        };
+        tasks.build_task().name("Field telemetry writer").spawn(
+            self.clone()
+                .write_telemetry(telemetry_receiver, cancellation_token.clone()),
+        )?;
+
         let run_field_tasks = async {
             while let Some(res) = tasks.join_next().await {
                 res.context("Field tasks stopped unexpectedly")??;
@@ -237,6 +630,167 @@ impl Field {
         Ok(())
     }
 
+    /// Brings back the event settings, driver station assignments and uncleared alarms saved
+    /// before the FMS last stopped. The field always comes back idle with every robot
+    /// disabled, so a match that was still running is recorded as aborted and raises
+    /// FMS_RECOVERED.
+    fn restore(&self) -> anyhow::Result<()> {
+        let database = self.database();
+        let event_settings = database.event_settings()?;
+        let assignments = database.driverstation_assignments()?;
+        let alarms = database.uncleared_alarms()?;
+        let interrupted_matches = database.unfinished_match_records()?;
+
+        if event_settings.is_none()
+            && assignments.is_empty()
+            && alarms.is_empty()
+            && interrupted_matches.is_empty()
+        {
+            return Ok(());
+        }
+
+        if let Some(event_settings) = event_settings {
+            let mut raw = self.raw.write().unwrap();
+            raw.event_name = event_settings.event_name;
+            raw.tournament_level = event_settings.tournament_level;
+            raw.match_number = event_settings.match_number;
+            raw.play_number = event_settings.play_number;
+            for (tournament_level, profile_name) in event_settings.timing_profiles {
+                // The profile may have been removed from the config since it was chosen
+                let restored = raw
+                    .timings
+                    .set_profile_name(tournament_level, &profile_name);
+                if let Err(e) = restored {
+                    warn!("Could not restore the {tournament_level} timing profile: {e}");
+                }
+            }
+        }
+
+        let driverstations = self.driverstations();
+        for assignment in assignments.iter() {
+            let ds = driverstations
+                .add_driverstation(assignment.team_number, assignment.alliance_station)
+                .with_context(|| {
+                    format!(
+                        "Could not restore driver station {}",
+                        assignment.team_number
+                    )
+                })?;
+            if let Some(expected_ip) = assignment.expected_ip {
+                ds.update_expected_ip(expected_ip);
+            }
+        }
+
+        let alarm_count = alarms.len();
+        self.alarm_handler().restore_alarms(alarms);
+
+        let now = chrono::Utc::now().timestamp();
+        for match_record in interrupted_matches.iter() {
+            database.finish_match_record(match_record.id, now, MatchState::Aborted)?;
+        }
+
+        self.audit_log().record(
+            SYSTEM_ACTOR,
+            "restore",
+            json!({}),
+            None,
+            Some(json!({
+                "eventName": self.event_name(),
+                "tournamentLevel": self.tournament_level().to_string(),
+                "matchNumber": self.match_number(),
+                "playNumber": self.play_number(),
+                "driverStations": assignments.len(),
+                "alarms": alarm_count,
+                "interruptedMatches": interrupted_matches
+                    .iter()
+                    .map(|match_record| match_record.id)
+                    .collect::<Vec<_>>(),
+            })),
+        );
+
+        info!(
+            "Restored {} {} match {} play {} with {} driver stations and {} uncleared alarms",
+            self.event_name(),
+            self.tournament_level(),
+            self.match_number(),
+            self.play_number(),
+            assignments.len(),
+            alarm_count
+        );
+
+        for match_record in interrupted_matches.iter() {
+            warn!(
+                "{} match {} play {} was interrupted by an FMS restart and recorded as aborted",
+                match_record.tournament_level, match_record.match_number, match_record.play_number
+            );
+        }
+
+        let description = if interrupted_matches.is_empty() {
+            "FMS restarted and restored the event, driver stations and alarms. Check them before the next match."
+        } else {
+            "FMS restarted during a match, which was recorded as aborted. Every robot is disabled."
+        };
+        if let Err(e) = self.alarm_handler().throw_alarm(
+            FMSAlarmType::Warning,
+            "FMS_RECOVERED",
+            description,
+            "fms.field",
+            "fms.field",
+            false,
+            false,
+        ) {
+            warn!("Could not throw recovery alarm: {}", e);
+        }
+
+        Ok(())
+    }
+
+    fn save_event_settings(&self) {
+        let (database, event_settings) = {
+            let raw = self.raw.read().unwrap();
+            let event_settings = EventSettings {
+                event_name: raw.event_name.clone(),
+                tournament_level: raw.tournament_level,
+                match_number: raw.match_number,
+                play_number: raw.play_number,
+                timing_profiles: TournamentLevel::ALL
+                    .into_iter()
+                    .map(|tournament_level| {
+                        let profile_name = raw.timings.profile_name(tournament_level);
+                        (tournament_level, profile_name.to_string())
+                    })
+                    .collect(),
+            };
+            (raw.database.clone(), event_settings)
+        };
+        if let Err(e) = database.save_event_settings(&event_settings) {
+            error!("Error saving event settings: {}", e);
+        }
+    }
+
+    fn notify(&self, update: FieldUpdate) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.updates.send(update);
+    }
+
+    fn set_udp_online(&self, udp_online: bool) {
+        let mut raw = self.raw.write().unwrap();
+        if raw.udp_online != udp_online {
+            raw.udp_online = udp_online;
+            drop(raw);
+            self.notify(FieldUpdate::FieldState);
+        }
+    }
+
+    fn set_tcp_online(&self, tcp_online: bool) {
+        let mut raw = self.raw.write().unwrap();
+        if raw.tcp_online != tcp_online {
+            raw.tcp_online = tcp_online;
+            drop(raw);
+            self.notify(FieldUpdate::FieldState);
+        }
+    }
+
     async fn listen_for_udp_messages_with_retry_loop(
         self,
         addr: SocketAddr,
@@ -250,18 +804,12 @@ impl Field {
                     .context(new_bind_err("UDP", addr));
                 if socket.is_err() {
                     error!("{}", socket.err().unwrap());
-                    {
-                        let mut raw_field = self.raw.write().unwrap();
-                        raw_field.udp_online = false;
-                    }
+                    self.set_udp_online(false);
                     tokio::time::sleep(tokio::time::Duration::from_secs(15)).await;
                     continue;
                 }
                 let socket = socket.unwrap();
-                {
-                    let mut raw_field = self.raw.write().unwrap();
-                    raw_field.udp_online = false;
-                }
+                self.set_udp_online(true);
                 let driverstations = self.driverstations();
 
                 let mut buf = vec![0; 1024];
@@ -269,12 +817,13 @@ impl Field {
                 loop {
                     match socket.recv_from(&mut buf).await {
                         Ok((size, _)) => {
-                            if let Err(e) = driverstations
-                                .decode_udp_message(buf[..size].to_vec())
-                                .await
-                                && e.to_string() != "unexpected end of file"
-                            {
-                                error!("Error decoding UDP message: {}", e);
+                            if let Err(e) = driverstations.decode_udp_message(&buf[..size]) {
+                                if matches!(e.downcast_ref(), Some(ProtocolError::Truncated { .. }))
+                                {
+                                    debug!("Ignoring truncated UDP message: {}", e);
+                                } else {
+                                    error!("Error decoding UDP message: {}", e);
+                                }
                             }
                         }
                         Err(e) => {
@@ -304,18 +853,12 @@ impl Field {
                     .context(new_bind_err("TCP", addr));
                 if listener.is_err() {
                     error!("{}", listener.err().unwrap());
-                    {
-                        let mut raw_field = self.raw.write().unwrap();
-                        raw_field.tcp_online = false;
-                    }
+                    self.set_tcp_online(false);
                     tokio::time::sleep(tokio::time::Duration::from_secs(15)).await;
                     continue;
                 }
                 let listener = listener.unwrap();
-                {
-                    let mut raw_field = self.raw.write().unwrap();
-                    raw_field.tcp_online = true;
-                }
+                self.set_tcp_online(true);
                 let driverstations = self.driverstations();
 
                 info!("Listening for TCP connections on {}", addr);
@@ -349,8 +892,10 @@ impl Field {
 
         let interval_tick_loop = async {
             loop {
-                interval.tick().await;
+                let due = interval.tick().await;
                 self.tick();
+                self.raw.write().unwrap().last_tick_at = Some(Instant::now());
+                self.metrics().record_tick_latency("field", due.elapsed());
             }
         };
 
@@ -360,13 +905,247 @@ impl Field {
         }
     }
 
+    /// Writes every thrown, released and cleared alarm to the database
+    async fn record_alarms(
+        self,
+        mut alarm_events: broadcast::Receiver<FMSAlarmEvent>,
+        cancellation_token: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let database = self.database();
+
+        let record_loop = async {
+            loop {
+                match alarm_events.recv().await {
+                    Ok(event) => {
+                        let cleared = event.event_type == FMSAlarmEventType::Cleared;
+                        if let Err(e) = database.save_alarm(&event.alarm, cleared) {
+                            error!("Error saving alarm {}: {}", event.alarm.code, e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Alarm recorder missed {} alarm events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        };
+
+        tokio::select! {
+            _ = cancellation_token.cancelled() => Ok(()),
+            _ = record_loop => Err(anyhow::anyhow!("Alarm recorder closed unexpectedly")),
+        }
+    }
+
+    /// Writes driver station telemetry to the database in batches, so that the TCP stream
+    /// handlers never wait on a database write.
+    async fn write_telemetry(
+        self,
+        mut telemetry_receiver: mpsc::Receiver<TelemetryRecord>,
+        cancellation_token: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let database = self.database();
+        let mut batch = Vec::with_capacity(TELEMETRY_BATCH_SIZE);
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            let remaining = TELEMETRY_BATCH_SIZE - batch.len();
+            let closed = tokio::select! {
+                _ = cancellation_token.cancelled() => true,
+                received = telemetry_receiver.recv_many(&mut batch, remaining) => {
+                    if batch.len() < TELEMETRY_BATCH_SIZE && received != 0 {
+                        continue;
+                    }
+                    received == 0
+                }
+                _ = interval.tick() => false,
+            };
+
+            if !batch.is_empty() {
+                let records = std::mem::take(&mut batch);
+                let database = database.clone();
+                let res =
+                    tokio::task::spawn_blocking(move || database.insert_telemetry(&records)).await;
+                match res {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => error!("Error saving driver station telemetry: {}", e),
+                    // Losing one batch is better than losing the field
+                    Err(e) => error!("Saving driver station telemetry panicked: {}", e),
+                }
+            }
+
+            if closed {
+                return Ok(());
+            }
+        }
+    }
+
+    fn record_telemetry(&self, record: TelemetryRecord) {
+        if let Err(e) = self.telemetry.try_send(record) {
+            warn!("Dropped driver station telemetry: {}", e);
+        }
+    }
+
     fn tick(&self) {
         // Respond to active faults
         if self
             .alarm_handler()
             .is_target_faulted(self.alarm_target().as_str())
         {
+            let previous_state = self.match_state();
             self.match_abort();
+            if previous_state.is_running() {
+                self.audit_log().record(
+                    SYSTEM_ACTOR,
+                    "abortMatch",
+                    json!({ "reason": "Field faulted" }),
+                    Some(json!(previous_state.to_string())),
+                    Some(json!(self.match_state().to_string())),
+                );
+            }
+        }
+
+        // Advance the match once the current period runs out
+        let match_state = self.match_state();
+        if match_state.is_running()
+            && !self.match_paused()
+            && self.timer().current_time_remaining().is_zero()
+        {
+            let next_state = match match_state {
+                MatchState::Auto => MatchState::Transition,
+                MatchState::Transition => MatchState::Teleop,
+                _ => MatchState::PostMatch,
+            };
+            self.set_match_state(next_state);
+            self.audit_log().record(
+                SYSTEM_ACTOR,
+                "advanceMatch",
+                json!({}),
+                Some(json!(match_state.to_string())),
+                Some(json!(next_state.to_string())),
+            );
+        }
+
+        // Flag the field as safe once the post match hold is over
+        let hold_over = {
+            let mut raw = self.raw.write().unwrap();
+            raw.post_match_safe_at
+                .take_if(|safe_at| *safe_at <= Instant::now())
+                .is_some()
+        };
+        if hold_over && self.set_is_safe(true).is_ok() {
+            self.audit_log().record(
+                SYSTEM_ACTOR,
+                "setIsSafe",
+                json!({ "isSafe": true }),
+                Some(json!(false)),
+                Some(json!(true)),
+            );
+        }
+    }
+
+    /// Applies everything that comes with entering a new match state: the ds mode, the
+    /// period timer and the commanded enable of every driver station.
+    fn set_match_state(&self, match_state: MatchState) {
+        {
+            let mut raw = self.raw.write().unwrap();
+            raw.match_state = match_state;
+            raw.match_paused = false;
+            raw.post_match_safe_at = None;
+            let profile = raw.timings.profile(raw.tournament_level);
+
+            match match_state {
+                MatchState::Idle | MatchState::PreStart => {
+                    raw.ds_mode = enums::Mode::Autonomous;
+                    raw.time_left = difftimer::DiffTimer::new(profile.auto(), false);
+                }
+                MatchState::Auto => {
+                    raw.ds_mode = enums::Mode::Autonomous;
+                    raw.time_left = difftimer::DiffTimer::new(profile.auto(), true);
+                }
+                MatchState::Transition => {
+                    raw.ds_mode = enums::Mode::TeleOp;
+                    raw.time_left = difftimer::DiffTimer::new(profile.transition(), true);
+                }
+                MatchState::Teleop => {
+                    raw.ds_mode = enums::Mode::TeleOp;
+                    raw.time_left = difftimer::DiffTimer::new(profile.teleop(), true);
+                }
+                MatchState::PostMatch => {
+                    raw.time_left = difftimer::DiffTimer::new(Duration::ZERO, false);
+                    raw.post_match_safe_at = Some(Instant::now() + profile.post_match_hold());
+                }
+                MatchState::Aborted => {
+                    raw.time_left = raw.time_left.stop();
+                    raw.post_match_safe_at = Some(Instant::now() + profile.post_match_hold());
+                }
+            }
+        }
+
+        let enabled = match_state.is_enabled_period();
+        for ds in self.driverstations().get_all_driverstations() {
+            if match_state == MatchState::Idle {
+                ds.clear_manual_disable();
+            }
+            ds.set_commanded_enabled(enabled && !ds.manually_disabled());
+            match match_state {
+                MatchState::Idle => {
+                    ds.clear_emergency_stop();
+                    ds.clear_autonomous_stop();
+                }
+                MatchState::Transition | MatchState::Teleop => ds.clear_autonomous_stop(),
+                _ => {}
+            }
+        }
+
+        self.record_match_state(match_state);
+
+        info!("Match state set to {match_state}");
+        self.notify(FieldUpdate::FieldState);
+    }
+
+    /// Opens a match record when a match starts and closes it when the match ends
+    fn record_match_state(&self, match_state: MatchState) {
+        let database = self.database();
+        let now = chrono::Utc::now().timestamp();
+        match match_state {
+            MatchState::Auto => {
+                let res = database.insert_match_record(
+                    &self.event_name(),
+                    self.tournament_level(),
+                    self.match_number(),
+                    self.play_number(),
+                    now,
+                );
+                match res {
+                    Ok(id) => {
+                        let mut raw = self.raw.write().unwrap();
+                        raw.current_match_id = Some(id);
+                    }
+                    Err(e) => error!("Error saving match record: {}", e),
+                }
+            }
+            MatchState::PostMatch | MatchState::Aborted => {
+                let current_match_id = self.raw.read().unwrap().current_match_id;
+                if let Some(id) = current_match_id
+                    && let Err(e) = database.finish_match_record(id, now, match_state)
+                {
+                    error!("Error saving match record: {}", e);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn record_match_result(&self, committed: bool) {
+        let current_match_id = {
+            let mut raw = self.raw.write().unwrap();
+            raw.current_match_id.take()
+        };
+        if let Some(id) = current_match_id
+            && let Err(e) = self.database().set_match_record_committed(id, committed)
+        {
+            error!("Error saving match record: {}", e);
         }
     }
 }
@@ -377,3 +1156,291 @@ fn new_bind_err(conn_type: &str, addr: SocketAddr) -> String {
         conn_type, addr
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn test_field() -> Field {
+        let database = Database::open(Path::new(":memory:")).unwrap();
+        Field::new(MatchTimings::default(), database).unwrap()
+    }
+
+    fn is_recovered(field: &Field) -> bool {
+        field
+            .alarm_handler()
+            .active_alarms()
+            .iter()
+            .any(|alarm| alarm.code == "FMS_RECOVERED")
+    }
+
+    #[test]
+    fn first_start_is_not_a_recovery() {
+        let field = test_field();
+        assert!(!is_recovered(&field));
+    }
+
+    #[test]
+    fn restart_after_a_finished_match_is_a_recovery() {
+        let field = test_field();
+        field.set_event_name("2026test".to_string()).unwrap();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+        field.stop_match().unwrap();
+
+        let restarted = Field::new(MatchTimings::default(), field.database()).unwrap();
+        assert_eq!(restarted.event_name(), "2026test");
+        assert!(is_recovered(&restarted));
+        assert!(
+            restarted
+                .database()
+                .unfinished_match_records()
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn restart_keeps_the_chosen_timing_profile() {
+        let mut timings: MatchTimings = toml::from_str(
+            "[profiles.short]
+            auto_secs = 5
+            transition_secs = 1
+            teleop_secs = 20
+            endgame_warning_secs = 10
+            post_match_hold_secs = 1",
+        )
+        .unwrap();
+        timings.validate().unwrap();
+        let database = Database::open(Path::new(":memory:")).unwrap();
+        let field = Field::new(timings.clone(), database).unwrap();
+        field
+            .set_timing_profile(TournamentLevel::Qualification, "short")
+            .unwrap();
+
+        let restarted = Field::new(timings, field.database()).unwrap();
+        assert_eq!(
+            restarted
+                .timings()
+                .profile_name(TournamentLevel::Qualification),
+            "short"
+        );
+        assert_eq!(
+            restarted.timings().profile_name(TournamentLevel::Playoff),
+            "default"
+        );
+    }
+
+    #[test]
+    fn restart_during_a_match_aborts_it() {
+        let field = test_field();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+
+        let restarted = Field::new(MatchTimings::default(), field.database()).unwrap();
+        assert!(is_recovered(&restarted));
+        assert_eq!(restarted.match_state(), MatchState::Idle);
+        let match_records = restarted.database().match_records().unwrap();
+        assert_eq!(match_records.len(), 1);
+        assert_eq!(
+            match_records[0].final_state.as_deref(),
+            Some(MatchState::Aborted.to_string().as_str())
+        );
+        assert!(match_records[0].ended_at.is_some());
+    }
+
+    #[test]
+    fn match_does_not_start_on_a_safe_field() {
+        let field = test_field();
+        assert!(field.is_safe());
+        assert!(field.start_match(true).is_err());
+        assert_eq!(field.match_state(), MatchState::Idle);
+    }
+
+    #[test]
+    fn override_does_not_skip_field_checks() {
+        let field = test_field();
+        field.set_is_safe(false).unwrap();
+        field
+            .alarm_handler()
+            .throw_alarm(
+                FMSAlarmType::Fault,
+                "TEST_FAULT",
+                "Test fault",
+                "fms.field",
+                "fms.field",
+                true,
+                false,
+            )
+            .unwrap();
+        assert!(!field.prestart_check().can_override());
+        assert!(field.start_match(true).is_err());
+        assert_eq!(field.match_state(), MatchState::Idle);
+    }
+
+    #[tokio::test]
+    async fn started_match_stays_in_auto_across_a_tick() {
+        let field = test_field();
+        let ds = field
+            .driverstations()
+            .add_driverstation(5276, AllianceStation::Red1)
+            .unwrap();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+        assert!(ds.enabled());
+
+        field.driverstations().tick().await;
+        field.tick();
+
+        assert_eq!(field.match_state(), MatchState::Auto);
+        assert!(
+            !field
+                .alarm_handler()
+                .is_target_faulted(field.alarm_target().as_str())
+        );
+        assert!(ds.enabled());
+    }
+
+    #[test]
+    fn emergency_stopped_station_cannot_be_removed_mid_match() {
+        let field = test_field();
+        let driverstations = field.driverstations();
+        let ds = driverstations
+            .add_driverstation(5276, AllianceStation::Blue3)
+            .unwrap();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+        ds.emergency_stop("test");
+
+        assert!(driverstations.delete_driverstation(5276).is_err());
+        let ds = driverstations
+            .get_driverstation_by_team_number(5276)
+            .unwrap();
+        assert!(ds.emergency_stopped());
+        assert_eq!(ds.control_history().len(), 1);
+    }
+
+    #[test]
+    fn station_cannot_be_enabled_outside_an_enabled_period() {
+        let field = test_field();
+        let ds = field
+            .driverstations()
+            .add_driverstation(5276, AllianceStation::Red1)
+            .unwrap();
+        field.set_is_safe(false).unwrap();
+        for match_state in [
+            MatchState::Idle,
+            MatchState::PreStart,
+            MatchState::Transition,
+            MatchState::PostMatch,
+            MatchState::Aborted,
+        ] {
+            field.set_match_state(match_state);
+            assert!(ds.enable("test").is_err(), "enabled during {match_state}");
+            assert!(!ds.commanded_enabled());
+        }
+    }
+
+    #[test]
+    fn station_cannot_be_enabled_while_the_match_is_paused() {
+        let field = test_field();
+        let ds = field
+            .driverstations()
+            .add_driverstation(5276, AllianceStation::Red1)
+            .unwrap();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+        field.pause_match().unwrap();
+
+        assert!(ds.enable("test").is_err());
+        assert!(!ds.commanded_enabled());
+    }
+
+    #[test]
+    fn station_cannot_be_enabled_on_a_safe_field() {
+        let field = test_field();
+        let ds = field
+            .driverstations()
+            .add_driverstation(5276, AllianceStation::Red1)
+            .unwrap();
+        field.set_match_state(MatchState::Auto);
+        ds.disable("test");
+        assert!(field.is_safe());
+
+        assert!(ds.enable("test").is_err());
+        assert!(!ds.commanded_enabled());
+    }
+
+    #[test]
+    fn manually_disabled_station_stays_disabled_into_teleop() {
+        let field = test_field();
+        let ds = field
+            .driverstations()
+            .add_driverstation(5276, AllianceStation::Red1)
+            .unwrap();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+        ds.disable("test");
+
+        field.pause_match().unwrap();
+        field.unpause_match().unwrap();
+        assert!(!ds.commanded_enabled());
+
+        field.set_match_state(MatchState::Transition);
+        field.set_match_state(MatchState::Teleop);
+        assert!(!ds.commanded_enabled());
+
+        ds.enable("test").unwrap();
+        assert!(ds.enabled());
+    }
+
+    #[test]
+    fn manual_disable_is_cleared_for_the_next_match() {
+        let field = test_field();
+        let ds = field
+            .driverstations()
+            .add_driverstation(5276, AllianceStation::Red1)
+            .unwrap();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+        ds.disable("test");
+        field.stop_match().unwrap();
+        field.commit_match().unwrap();
+        assert!(!ds.manually_disabled());
+
+        field.start_match(true).unwrap();
+        assert!(ds.enabled());
+    }
+
+    #[test]
+    fn field_cannot_be_marked_safe_mid_match() {
+        let field = test_field();
+        let ds = field
+            .driverstations()
+            .add_driverstation(5276, AllianceStation::Red1)
+            .unwrap();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+
+        assert!(field.set_is_safe(true).is_err());
+        assert!(!field.is_safe());
+        assert!(ds.enabled());
+
+        field.stop_match().unwrap();
+        field.set_is_safe(true).unwrap();
+        assert!(field.is_safe());
+    }
+
+    #[test]
+    fn aborted_match_schedules_the_field_safe() {
+        let field = test_field();
+        field.set_is_safe(false).unwrap();
+        field.start_match(true).unwrap();
+        field.stop_match().unwrap();
+
+        assert_eq!(field.match_state(), MatchState::Aborted);
+        assert!(field.raw.read().unwrap().post_match_safe_at.is_some());
+    }
+}
@@ -1,12 +1,11 @@
 use std::{
-    convert::TryInto,
-    io::Cursor,
     net::{IpAddr, SocketAddr},
     sync::{Arc, RwLock},
+    time::Instant,
 };
 
 use anyhow::Context;
-use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use chrono::{DateTime, Local, Utc};
 use log::*;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -20,7 +19,9 @@ use tokio_util::sync::CancellationToken;
 use super::{
     Field,
     driverstation::{DriverStation, DriverStationLogData, DriverStationLogMessage},
-    enums::{AllianceStation, DriverstationStatus, Mode, VersionData, VersionType},
+    enums::{AllianceStation, DriverstationStatus},
+    packetstats::UdpPacketStats,
+    protocol::{self, ControlPacket, DsTcpPacket, FmsTcpPacket, PacketTime},
 };
 
 struct RawDriverStationConnection {
@@ -31,8 +32,16 @@ struct RawDriverStationConnection {
     tcp_writer: Option<Arc<tokio::sync::Mutex<OwnedWriteHalf>>>,
     udp_socket: Option<Arc<UdpSocket>>,
     ip_address: IpAddr,
+    /// The team number the driver station announced itself as
+    team_number: Option<u16>,
     udp_outgoing_sequence_num: u16,
     last_udp_packet_reception: DateTime<Utc>,
+    udp_packet_stats: UdpPacketStats,
+    /// The station info, event code and game data last sent over TCP, each `None` until the
+    /// first time it is sent
+    sent_station_info: Option<(AllianceStation, DriverstationStatus)>,
+    sent_event_code: Option<String>,
+    sent_game_data: Option<String>,
 }
 
 /// Represents the long-lived connection to the driver station
@@ -65,6 +74,13 @@ impl DriverStationConnection {
         raw.parent.clone()
     }
 
+    /// The team number the driver station announced itself as, which is only known once it
+    /// has sent its team number packet
+    pub fn team_number(&self) -> Option<u16> {
+        let raw = self.raw.read().unwrap();
+        raw.team_number
+    }
+
     pub fn ip_address(&self) -> IpAddr {
         let raw = self.raw.read().unwrap();
         raw.ip_address
@@ -75,6 +91,28 @@ impl DriverStationConnection {
         raw.last_udp_packet_reception
     }
 
+    pub fn udp_packet_stats(&self) -> UdpPacketStats {
+        let raw = self.raw.read().unwrap();
+        raw.udp_packet_stats.clone()
+    }
+
+    /// The status the driver station is told about its position: `Good` when it is connected
+    /// from where it is expected, `Bad` when its ip address is outside of the expected range,
+    /// and `Waiting` when it is not part of this match.
+    pub fn station_status(&self) -> DriverstationStatus {
+        let Some(ds) = self.parent() else {
+            return DriverstationStatus::Waiting;
+        };
+
+        if let Some(expected_ip) = ds.expected_ip()
+            && !expected_ip.contains(&self.ip_address())
+        {
+            return DriverstationStatus::Bad;
+        }
+
+        DriverstationStatus::Good
+    }
+
     pub async fn kill(&self) {
         let tcp_writer = {
             let mut raw = self.raw.write().unwrap();
@@ -82,9 +120,13 @@ impl DriverStationConnection {
         };
         if let Some(tcp_writer) = tcp_writer {
             let mut tcp_writer = tcp_writer.lock().await;
+            self.field()
+                .driverstations()
+                .unregister_connection(self.uuid());
             if let Some(ds) = self.parent() {
                 ds.remove_active_connection();
                 ds.set_confirmed_state(None);
+                ds.clear_diagnostics();
                 info!(
                     "Driver station {} disconnected (Conn ID: {})",
                     ds.team_number(),
@@ -110,8 +152,13 @@ impl DriverStationConnection {
             tcp_writer: None,
             udp_socket: None,
             ip_address,
+            team_number: None,
             udp_outgoing_sequence_num: 0,
             last_udp_packet_reception: Utc::now(),
+            udp_packet_stats: UdpPacketStats::default(),
+            sent_station_info: None,
+            sent_event_code: None,
+            sent_game_data: None,
             uuid: uuid::Uuid::new_v4(),
         };
 
@@ -151,12 +198,13 @@ impl DriverStationConnection {
         Ok(())
     }
 
-    pub(super) fn update_last_udp_packet_reception(&self, time: DateTime<Utc>) {
+    pub(super) fn record_udp_packet(&self, sequence_num: u16, time: DateTime<Utc>) {
         let mut raw = self.raw.write().unwrap();
         raw.last_udp_packet_reception = time;
+        raw.udp_packet_stats.record(sequence_num, Instant::now());
     }
 
-    fn set_parent(&self, parent: Option<DriverStation>) {
+    pub(super) fn set_parent(&self, parent: Option<DriverStation>) {
         let mut raw = self.raw.write().unwrap();
         raw.parent = parent;
     }
@@ -168,20 +216,27 @@ impl DriverStationConnection {
     ) -> anyhow::Result<()> {
         let mut read_stream = async || -> anyhow::Result<()> {
             loop {
-                let mut buffer = [0; 2];
-                tcp_reader.read_exact(&mut buffer).await?;
-                let mut reader = Cursor::new(buffer);
-                let packet_length = reader.read_u16().await?;
+                let mut prefix = [0; 2];
+                tcp_reader.read_exact(&mut prefix).await?;
+                let packet_length = protocol::frame_length(prefix);
 
-                let mut buffer = vec![0; packet_length as usize];
+                let mut buffer = vec![0; packet_length];
                 tcp_reader.read_exact(&mut buffer).await?;
-                let mut reader = Cursor::new(buffer);
-                let id = reader.read_u8().await?;
 
-                match id {
-                    0x18 => {
-                        // Team Number packet
-                        let team_number = reader.read_u16().await?;
+                let packet = match DsTcpPacket::decode(&buffer) {
+                    Ok(packet) => packet,
+                    Err(e) => {
+                        warn!(
+                            "Received a malformed TCP packet from a driverstation: {}",
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                match packet {
+                    DsTcpPacket::TeamNumber(team_number) => {
+                        self.raw.write().unwrap().team_number = Some(team_number);
                         if let Some(ds) = self
                             .field()
                             .driverstations()
@@ -200,103 +255,48 @@ impl DriverStationConnection {
                             );
                         }
 
-                        self.send_tcp_station_info().await?;
-                        self.send_tcp_event_code().await?;
+                        self.sync_tcp_state().await?;
                     }
-                    0x00..=0x07 => {
-                        // Version Codes
-                        //TODO Maybe use regex?
-                        let mut version_unparsed = String::new();
-                        reader.read_to_string(&mut version_unparsed).await.ok();
-                        let split: Vec<&str> = version_unparsed.split(">").collect();
-                        let (status, version) = if split.len() > 1 {
-                            (
-                                split[0].trim_start_matches("<").to_string(),
-                                split[1].to_string(),
-                            )
-                        } else {
-                            (String::new(), String::new())
-                        };
-
-                        let version_type = VersionType::from_byte(id).unwrap();
-                        let version = VersionData {
-                            version_type,
-                            status,
-                            version,
-                        };
-
+                    DsTcpPacket::Version(version) => {
                         if let Some(ds) = self.parent() {
-                            ds.set_version(version_type, version);
+                            ds.set_version(version.version_type, version);
                         }
                     }
-                    0x16 => {
-                        // Log Data Packet
-                        let timestamp = Utc::now().timestamp() as u64;
-
-                        let trip_time = reader.read_u8().await? / 2;
-                        let lost_packets = reader.read_u8().await?;
-
-                        let voltage_byte = reader.read_u16().await?;
-                        let voltage = (voltage_byte >> 8 & 0xff) as f32
-                            + ((voltage_byte & 0xff) as f32 / 256.0);
-
-                        let status_byte = reader.read_u8().await?;
-                        let brownout = (status_byte >> 7 & 0x01) == 1;
-                        let watchdog = (status_byte >> 6 & 0x01) == 1;
-                        let ds_teleop = (status_byte >> 5 & 0x01) == 1;
-                        let ds_auto = (status_byte >> 4 & 0x01) == 1;
-                        let ds_disable = (status_byte >> 3 & 0x01) == 1;
-                        let robot_teleop = (status_byte >> 2 & 0x01) == 1;
-                        let robot_auto = (status_byte >> 1 & 0x01) == 1;
-                        let robot_disable = (status_byte & 0x01) == 1;
-
-                        let can_utilization = reader.read_u8().await? / 2;
-                        let signal = reader.read_u8().await? / 2;
-                        let bandwidth = reader.read_u16().await? as f32 / 256.0;
-
+                    DsTcpPacket::LogData(log_data) => {
                         if let Some(ds) = self.parent() {
                             ds.record_log_data(DriverStationLogData {
-                                timestamp,
-                                trip_time,
-                                lost_packets,
-                                voltage,
-                                brownout,
-                                watchdog,
-                                ds_teleop,
-                                ds_auto,
-                                ds_disable,
-                                robot_teleop,
-                                robot_auto,
-                                robot_disable,
-                                can_utilization,
-                                signal,
-                                bandwidth,
+                                timestamp: Utc::now().timestamp() as u64,
+                                trip_time: log_data.trip_time,
+                                lost_packets: log_data.lost_packets,
+                                voltage: log_data.voltage,
+                                brownout: log_data.brownout,
+                                watchdog: log_data.watchdog,
+                                ds_teleop: log_data.ds_teleop,
+                                ds_auto: log_data.ds_auto,
+                                ds_disable: log_data.ds_disable,
+                                robot_teleop: log_data.robot_teleop,
+                                robot_auto: log_data.robot_auto,
+                                robot_disable: log_data.robot_disable,
+                                can_utilization: log_data.can_utilization,
+                                signal: log_data.signal,
+                                bandwidth: log_data.bandwidth,
                             });
                         }
                     }
-                    0x17 => {
-                        // Log Message Packet
-                        let timestamp = Utc::now().timestamp() as u64;
-                        let _ = reader.read_u32().await?; // Message Count (Seems to always be 1?) - Chase
-                        let local_timestamp = reader.read_u64().await? - 2082844800; // Offset from LabView epoch to UNIX Epoch
-                        reader.read_u64().await?;
-                        let mut data = String::new();
-                        reader.read_u32().await?;
-                        reader.read_to_string(&mut data).await.ok();
-
+                    DsTcpPacket::LogMessage(log_message) => {
                         if let Some(ds) = self.parent() {
                             ds.add_log_message(DriverStationLogMessage {
-                                timestamp,
-                                local_timestamp,
-                                message: data,
+                                timestamp: Utc::now().timestamp() as u64,
+                                local_timestamp: log_message.timestamp,
+                                message: log_message.message,
                             });
                         }
                     }
-                    0x1d => { /* Keep-Alive Packet, doesn't need a reply */ }
-                    unknown_id => {
+                    DsTcpPacket::KeepAlive => { /* Keep-Alive Packet, doesn't need a reply */ }
+                    DsTcpPacket::Unknown { id, .. } => {
                         warn!(
                             "Received a TCP packet from a driverstation with an unknown id {:#x} and size {}",
-                            unknown_id, packet_length
+                            id, packet_length
                         );
                     }
                 }
@@ -320,65 +320,81 @@ impl DriverStationConnection {
     }
 
     async fn send_tcp_station_info(&self) -> anyhow::Result<()> {
-        let mut alliance_station = AllianceStation::None;
-        let mut status = DriverstationStatus::Waiting;
-
-        if let Some(ds) = self.parent() {
-            alliance_station = ds.alliance_station();
-            status = DriverstationStatus::Good;
-
-            if let Some(expected_ip) = ds.expected_ip()
-                && !expected_ip.contains(&self.ip_address())
-            {
-                status = DriverstationStatus::Bad;
-                info!(
-                    "Driver station {} is not expected to be connected from this IP address ({})",
-                    ds.team_number(),
-                    self.ip_address()
-                );
-            }
+        let alliance_station = self
+            .parent()
+            .map(|ds| ds.alliance_station())
+            .unwrap_or(AllianceStation::None);
+        let status = self.station_status();
+
+        if let DriverstationStatus::Bad = status
+            && let Some(ds) = self.parent()
+        {
+            info!(
+                "Driver station {} is not expected to be connected from this IP address ({})",
+                ds.team_number(),
+                self.ip_address()
+            );
         }
 
-        let mut packet = Cursor::new(Vec::new());
-        packet.write_u8(0x19).await?; //0x19 = ID For Station Info
-        packet.write_u8(alliance_station.to_byte()).await?;
-        packet.write_u8(status.to_byte()).await?;
-        let buffer = packet.into_inner();
+        self.send_tcp_packet(FmsTcpPacket::StationInfo {
+            alliance_station,
+            status,
+        })
+        .await
+    }
 
-        let mut outer_packet = Cursor::new(Vec::<u8>::new());
-        outer_packet
-            .write_u16(buffer.len().try_into().unwrap())
-            .await?;
-        outer_packet.write_all(&buffer).await?;
+    async fn send_tcp_event_code(&self) -> anyhow::Result<()> {
+        self.send_tcp_packet(FmsTcpPacket::EventCode(self.field().event_name()))
+            .await
+    }
 
-        let tcp_writer = {
+    /// Sends the station info, event code and game data that differ from what this driver
+    /// station was last sent, so that changes to its assignment, expected ip, the event name
+    /// or the game data reach it without a reconnect
+    pub(super) async fn sync_tcp_state(&self) -> anyhow::Result<()> {
+        let (sent_station_info, sent_event_code, sent_game_data) = {
             let raw_conn = self.raw.read().unwrap();
-            raw_conn
-                .tcp_writer
-                .clone()
-                .ok_or_else(|| anyhow::anyhow!("This DriverStationConnection is already closed"))?
+            (
+                raw_conn.sent_station_info,
+                raw_conn.sent_event_code.clone(),
+                raw_conn.sent_game_data.clone(),
+            )
         };
 
-        let mut tcp_writer = tcp_writer.lock().await;
-        tcp_writer.write_all(&outer_packet.into_inner()).await?;
+        let alliance_station = self
+            .parent()
+            .map(|ds| ds.alliance_station())
+            .unwrap_or(AllianceStation::None);
+        let station_info = (alliance_station, self.station_status());
+        if sent_station_info != Some(station_info) {
+            self.send_tcp_station_info().await?;
+            self.raw.write().unwrap().sent_station_info = Some(station_info);
+        }
+
+        let event_code = self.field().event_name();
+        if sent_event_code.as_ref() != Some(&event_code) {
+            self.send_tcp_event_code().await?;
+            self.raw.write().unwrap().sent_event_code = Some(event_code);
+        }
+
+        if let Some(ds) = self.parent() {
+            let game_data = self.field().released_game_data(ds.alliance_station());
+            if sent_game_data.as_ref() != Some(&game_data) {
+                self.send_tcp_game_data(&game_data).await?;
+                self.raw.write().unwrap().sent_game_data = Some(game_data);
+            }
+        }
 
         Ok(())
     }
 
-    async fn send_tcp_event_code(&self) -> anyhow::Result<()> {
-        let event_code = self.field().event_name();
-
-        let mut packet = Cursor::new(Vec::new());
-        packet.write_u8(0x14).await?; //ID For Event Code
-        packet.write_u8(event_code.len() as u8).await?;
-        packet.write_all(event_code.as_bytes()).await?;
-        let buffer = packet.into_inner();
+    async fn send_tcp_game_data(&self, game_data: &str) -> anyhow::Result<()> {
+        self.send_tcp_packet(FmsTcpPacket::GameData(game_data.to_string()))
+            .await
+    }
 
-        let mut outer_packet = Cursor::new(Vec::<u8>::new());
-        outer_packet
-            .write_u16(buffer.len().try_into().unwrap())
-            .await?;
-        outer_packet.write_all(&buffer).await?;
+    async fn send_tcp_packet(&self, packet: FmsTcpPacket) -> anyhow::Result<()> {
+        let buffer = packet.encode_framed()?;
 
         let tcp_writer = {
             let raw_conn = self.raw.read().unwrap();
@@ -389,7 +405,7 @@ impl DriverStationConnection {
         };
 
         let mut tcp_writer = tcp_writer.lock().await;
-        tcp_writer.write_all(&outer_packet.into_inner()).await?;
+        tcp_writer.write_all(&buffer).await?;
 
         Ok(())
     }
@@ -412,68 +428,30 @@ impl DriverStationConnection {
             (udp_socket, raw_conn.udp_outgoing_sequence_num)
         };
 
-        let mut packet = Cursor::new(Vec::new());
-        packet.write_u16(seq_num).await?;
-        packet.write_u8(0x00).await?; //Comm Version
-
         let driverstations = self.field().driverstations();
         let field = driverstations.get_field();
         let ip_address = self.ip_address();
 
-        let mut control_byte = 0x00;
-        match field.ds_mode() {
-            Mode::TeleOp => control_byte |= 0x00,
-            Mode::Test => control_byte |= 0x01,
-            Mode::Autonomous => control_byte |= 0x02,
-        }
-
-        if ds.enabled() {
-            control_byte |= 0x04
-        }
-
-        if field
+        // EStop DS if field is faulted
+        let field_faulted = field
             .alarm_handler()
-            .is_target_faulted(field.alarm_target().as_str())
-        {
-            // EStop DS if field is faulted
-            control_byte |= 0x80
+            .is_target_faulted(field.alarm_target().as_str());
+
+        let buffer = ControlPacket {
+            sequence_num: seq_num,
+            comm_version: 0x00,
+            emergency_stop: field_faulted || ds.emergency_stopped(),
+            enabled: ds.enabled(),
+            mode: field.ds_mode(),
+            request: 0x00,
+            alliance_station: ds.alliance_station(),
+            tournament_level: field.tournament_level(),
+            match_number: field.match_number(),
+            play_number: field.play_number(),
+            time: PacketTime::from_datetime(&Local::now()),
+            time_remaining: field.timer().current_time_remaining().as_secs() as u16,
         }
-
-        packet.write_u8(control_byte).await?;
-        packet.write_u8(0x00).await?; //Request Byte
-        packet.write_u8(ds.alliance_station().to_byte()).await?; //Alliance Station
-        packet
-            .write_u8(driverstations.get_field().tournament_level().to_byte())
-            .await?; //Tournament Level
-        packet
-            .write_u16(driverstations.get_field().match_number())
-            .await?; //Match Number
-        packet
-            .write_u8(driverstations.get_field().play_number())
-            .await?; //Play Number
-
-        let time = Local::now();
-        packet.write_u32(time.nanosecond() / 1000).await?;
-        packet.write_u8(time.second().try_into().unwrap()).await?;
-        packet.write_u8(time.minute().try_into().unwrap()).await?;
-        packet.write_u8(time.hour().try_into().unwrap()).await?;
-        packet.write_u8(time.day().try_into().unwrap()).await?;
-        packet.write_u8(time.month().try_into().unwrap()).await?;
-        packet
-            .write_u8((time.year() - 1900).try_into().unwrap())
-            .await?;
-
-        packet
-            .write_u16(
-                driverstations
-                    .get_field()
-                    .timer()
-                    .current_time_remaining()
-                    .as_secs() as u16,
-            )
-            .await?; //Time Remaining
-
-        let buffer = packet.into_inner();
+        .encode()?;
 
         udp_socket
             .send_to(&buffer, SocketAddr::from((ip_address, 1121)))
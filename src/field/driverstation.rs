@@ -1,37 +1,52 @@
 use std::{
-    collections::HashMap,
-    io::Cursor,
+    collections::{HashMap, VecDeque},
     net::IpAddr,
     sync::{Arc, RwLock},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, bail};
 use chrono::Utc;
 use cidr::AnyIpCidr;
 use log::*;
-use tokio::{io::AsyncReadExt, net::TcpStream};
+use tokio::{net::TcpStream, sync::broadcast::error::RecvError};
 use tokio_util::sync::CancellationToken;
 
-use crate::alarms::FMSAlarmType;
+use crate::{
+    alarms::FMSAlarmType,
+    database::{DriverStationAssignment, TelemetryFilter, TelemetryRecord},
+};
 
 use super::{
-    Field,
+    Field, FieldUpdate,
     connection::DriverStationConnection,
-    enums::{AllianceStation, Mode, VersionData, VersionType},
+    enums::{AllianceStation, DriverStationControl, MatchState, Mode, VersionData, VersionType},
+    protocol::{DriverStationTag, StatusPacket},
+    tags::DriverStationDiagnostics,
 };
 
+/// How many manual controls are kept per driver station. Every control is also recorded in
+/// the audit log.
+const CONTROL_HISTORY_LIMIT: usize = 64;
+/// How long a connection is kept without receiving UDP from its driver station
+const UDP_TIMEOUT: chrono::Duration = chrono::Duration::seconds(2);
+
 struct RawDriverStation {
     parent: DriverStations,
     team_number: u16,
     alliance_station: AllianceStation,
     commanded_enabled: bool,
+    emergency_stopped: bool,
+    autonomous_stopped: bool,
+    /// Set when an operator disables this driver station, so starting the next period or
+    /// unpausing the match does not enable it again
+    manually_disabled: bool,
+    control_history: VecDeque<DriverStationControlEvent>,
     expected_ip: Option<AnyIpCidr>,
     active_connection: Option<DriverStationConnection>,
     confirmed_state: Option<DriverStationConfirmedState>,
-    log_data: Vec<DriverStationLogData>,
+    diagnostics: Option<DriverStationDiagnostics>,
     versions: HashMap<VersionType, VersionData>,
-    log_messages: Vec<DriverStationLogMessage>,
 }
 
 #[derive(Clone)]
@@ -73,7 +88,98 @@ impl DriverStation {
             .alarm_handler()
             .is_target_faulted(self.alarm_target().as_str());
         let commanded_enabled = self.commanded_enabled();
-        commanded_enabled && !faulted
+        commanded_enabled && !faulted && !self.emergency_stopped() && !self.autonomous_stopped()
+    }
+
+    /// A latched E-Stop that holds until the field is reset for the next match
+    pub fn emergency_stopped(&self) -> bool {
+        let raw = self.raw.read().unwrap();
+        raw.emergency_stopped
+    }
+
+    /// An A-Stop that holds until the end of the autonomous period
+    pub fn autonomous_stopped(&self) -> bool {
+        let raw = self.raw.read().unwrap();
+        raw.autonomous_stopped
+    }
+
+    /// A manual disable that holds until the driver station is enabled again or the field is
+    /// reset for the next match
+    pub fn manually_disabled(&self) -> bool {
+        let raw = self.raw.read().unwrap();
+        raw.manually_disabled
+    }
+
+    /// The latest manual controls of this driver station, oldest first
+    pub fn control_history(&self) -> Vec<DriverStationControlEvent> {
+        let raw = self.raw.read().unwrap();
+        raw.control_history.iter().cloned().collect()
+    }
+
+    /// Enables a driver station that was manually disabled. Only allowed during the enabled
+    /// periods of a running match that is not paused, on a field that is not safe.
+    pub fn enable(&self, triggered_by: &str) -> anyhow::Result<()> {
+        let field = self.parent().get_field();
+        let match_state = field.match_state();
+        if !match_state.is_enabled_period() {
+            bail!(
+                "Cannot enable driver station {} while the field is in state {match_state}",
+                self.team_number()
+            );
+        }
+        if field.match_paused() {
+            bail!(
+                "Cannot enable driver station {} while the match is paused",
+                self.team_number()
+            );
+        }
+        if field.is_safe() {
+            bail!(
+                "Cannot enable driver station {} while the field is safe",
+                self.team_number()
+            );
+        }
+        if self.emergency_stopped() {
+            bail!("Driver station {} is emergency stopped", self.team_number());
+        }
+        if self.autonomous_stopped() {
+            bail!(
+                "Driver station {} is autonomous stopped",
+                self.team_number()
+            );
+        }
+        self.raw.write().unwrap().manually_disabled = false;
+        self.set_commanded_enabled(true);
+        self.record_control(DriverStationControl::Enable, triggered_by);
+        Ok(())
+    }
+
+    pub fn disable(&self, triggered_by: &str) {
+        self.raw.write().unwrap().manually_disabled = true;
+        self.set_commanded_enabled(false);
+        self.record_control(DriverStationControl::Disable, triggered_by);
+    }
+
+    pub fn emergency_stop(&self, triggered_by: &str) {
+        {
+            let mut raw = self.raw.write().unwrap();
+            raw.emergency_stopped = true;
+            raw.commanded_enabled = false;
+        }
+        self.record_control(DriverStationControl::EmergencyStop, triggered_by);
+    }
+
+    pub fn autonomous_stop(&self, triggered_by: &str) -> anyhow::Result<()> {
+        let match_state = self.parent().get_field().match_state();
+        if match_state != MatchState::Auto {
+            bail!("Cannot A-Stop a driver station while the field is in state {match_state}");
+        }
+        {
+            let mut raw = self.raw.write().unwrap();
+            raw.autonomous_stopped = true;
+        }
+        self.record_control(DriverStationControl::AutonomousStop, triggered_by);
+        Ok(())
     }
 
     pub fn expected_ip(&self) -> Option<AnyIpCidr> {
@@ -91,16 +197,32 @@ impl DriverStation {
         raw.confirmed_state
     }
 
-    pub fn log_data(&self) -> Vec<DriverStationLogData> {
+    /// What the driver station reported in the tagged section of its UDP packets. Changes here
+    /// are not sent to subscribers, as they come in with nearly every packet.
+    pub fn diagnostics(&self) -> Option<DriverStationDiagnostics> {
         let raw = self.raw.read().unwrap();
-        raw.log_data.clone()
-        //TODO Don't store in RAM, read from DB instead
+        raw.diagnostics.clone()
     }
 
-    pub fn log_messages(&self) -> Vec<DriverStationLogMessage> {
-        let raw = self.raw.read().unwrap();
-        raw.log_messages.clone()
-        //TODO Don't store in RAM, read from DB instead
+    /// Reads recorded log data from the database. Rows are written in batches, so the last
+    /// second of data may not be visible yet.
+    pub fn log_data(&self, filter: &TelemetryFilter) -> anyhow::Result<Vec<DriverStationLogData>> {
+        self.parent()
+            .get_field()
+            .database()
+            .log_data(self.team_number(), filter)
+    }
+
+    /// Reads recorded log messages from the database. Rows are written in batches, so the last
+    /// second of messages may not be visible yet.
+    pub fn log_messages(
+        &self,
+        filter: &TelemetryFilter,
+    ) -> anyhow::Result<Vec<DriverStationLogMessage>> {
+        self.parent()
+            .get_field()
+            .database()
+            .log_messages(self.team_number(), filter)
     }
 
     pub fn versions(&self) -> HashMap<VersionType, VersionData> {
@@ -116,6 +238,9 @@ impl DriverStation {
             raw.team_number,
             raw.expected_ip.unwrap()
         );
+        drop(raw);
+        self.save_assignment();
+        self.notify_changed();
     }
 
     // Internal API -->
@@ -126,12 +251,15 @@ impl DriverStation {
             team_number,
             alliance_station,
             commanded_enabled: false,
+            emergency_stopped: false,
+            autonomous_stopped: false,
+            manually_disabled: false,
+            control_history: VecDeque::new(),
             expected_ip: None,
             active_connection: None,
             confirmed_state: None,
-            log_data: Vec::new(),
+            diagnostics: None,
             versions: HashMap::new(),
-            log_messages: Vec::new(),
         };
 
         Self {
@@ -142,38 +270,157 @@ impl DriverStation {
     pub(super) fn set_version(&self, version_type: VersionType, version: VersionData) {
         let mut raw = self.raw.write().unwrap();
         raw.versions.insert(version_type, version);
+        drop(raw);
+        self.notify_changed();
     }
 
     pub(super) fn record_log_data(&self, log_data: DriverStationLogData) {
-        let mut raw = self.raw.write().unwrap();
-        raw.log_data.push(log_data);
-        //TODO Don't store in RAM, write to DB instead
+        let field = self.parent().get_field();
+        field
+            .metrics()
+            .record_log_data(self.team_number(), self.alliance_station(), &log_data);
+        field.record_telemetry(TelemetryRecord::LogData {
+            team_number: self.team_number(),
+            match_id: field.current_match_id(),
+            log_data,
+        });
     }
 
     pub(super) fn add_log_message(&self, log_message: DriverStationLogMessage) {
-        let mut raw = self.raw.write().unwrap();
-        raw.log_messages.push(log_message);
-        //TODO Don't store in RAM, write to DB instead
+        let field = self.parent().get_field();
+        field.record_telemetry(TelemetryRecord::LogMessage {
+            team_number: self.team_number(),
+            match_id: field.current_match_id(),
+            log_message,
+        });
     }
 
     pub(super) fn set_confirmed_state(&self, confirmed_state: Option<DriverStationConfirmedState>) {
         let mut raw = self.raw.write().unwrap();
+        let changed = match (raw.confirmed_state, confirmed_state) {
+            (Some(old), Some(new)) => old.differs_from(&new),
+            (None, None) => false,
+            _ => true,
+        };
         raw.confirmed_state = confirmed_state;
+        drop(raw);
+        if changed {
+            self.notify_changed();
+        }
+    }
+
+    pub(super) fn record_tags(&self, tags: Vec<DriverStationTag>) {
+        let mut raw = self.raw.write().unwrap();
+        let diagnostics = raw.diagnostics.get_or_insert_default();
+        for tag in tags {
+            diagnostics.apply(tag);
+        }
+    }
+
+    pub(super) fn clear_diagnostics(&self) {
+        let mut raw = self.raw.write().unwrap();
+        raw.diagnostics = None;
     }
 
     pub(super) fn remove_active_connection(&self) -> Option<DriverStationConnection> {
         let mut raw = self.raw.write().unwrap();
-        raw.active_connection.take()
+        let active_connection = raw.active_connection.take();
+        drop(raw);
+        if active_connection.is_some() {
+            self.notify_changed();
+        }
+        active_connection
     }
 
     pub(super) fn set_active_connection(&self, active_connection: DriverStationConnection) {
         let mut raw = self.raw.write().unwrap();
         raw.active_connection = Some(active_connection);
+        drop(raw);
+        self.notify_changed();
     }
 
     pub(super) fn set_commanded_enabled(&self, enabled: bool) {
         let mut raw = self.raw.write().unwrap();
+        let changed = raw.commanded_enabled != enabled;
         raw.commanded_enabled = enabled;
+        drop(raw);
+        if changed {
+            self.notify_changed();
+        }
+    }
+
+    pub(super) fn clear_manual_disable(&self) {
+        let mut raw = self.raw.write().unwrap();
+        let changed = raw.manually_disabled;
+        raw.manually_disabled = false;
+        drop(raw);
+        if changed {
+            self.notify_changed();
+        }
+    }
+
+    pub(super) fn clear_autonomous_stop(&self) {
+        let mut raw = self.raw.write().unwrap();
+        let changed = raw.autonomous_stopped;
+        raw.autonomous_stopped = false;
+        drop(raw);
+        if changed {
+            self.notify_changed();
+        }
+    }
+
+    pub(super) fn clear_emergency_stop(&self) {
+        let mut raw = self.raw.write().unwrap();
+        let changed = raw.emergency_stopped;
+        raw.emergency_stopped = false;
+        drop(raw);
+        if changed {
+            self.notify_changed();
+        }
+    }
+
+    fn save_assignment(&self) {
+        let assignment = DriverStationAssignment {
+            team_number: self.team_number(),
+            alliance_station: self.alliance_station(),
+            expected_ip: self.expected_ip(),
+        };
+        let res = self
+            .parent()
+            .get_field()
+            .database()
+            .save_driverstation_assignment(&assignment);
+        if let Err(e) = res {
+            error!(
+                "Error saving assignment of driverstation {}: {}",
+                assignment.team_number, e
+            );
+        }
+    }
+
+    fn notify_changed(&self) {
+        let team_number = self.team_number();
+        self.parent()
+            .get_field()
+            .notify(FieldUpdate::DriverStation { team_number });
+    }
+
+    fn record_control(&self, control: DriverStationControl, triggered_by: &str) {
+        let mut raw = self.raw.write().unwrap();
+        if raw.control_history.len() >= CONTROL_HISTORY_LIMIT {
+            raw.control_history.pop_front();
+        }
+        raw.control_history.push_back(DriverStationControlEvent {
+            control,
+            triggered_by: triggered_by.to_string(),
+            timestamp: Utc::now().timestamp() as u64,
+        });
+        info!(
+            "{} of driver station {} triggered by {}",
+            control, raw.team_number, triggered_by
+        );
+        drop(raw);
+        self.notify_changed();
     }
 
     async fn tick(&self) {
@@ -190,18 +437,17 @@ impl DriverStation {
         if let Some(conn) = self.active_connection()
             && conn.is_alive()
         {
-            if Utc::now().signed_duration_since(conn.last_udp_packet_reception())
-                > chrono::Duration::seconds(2)
-            {
+            if Utc::now().signed_duration_since(conn.last_udp_packet_reception()) > UDP_TIMEOUT {
                 conn.kill().await;
             } else {
                 let udp_result = conn.send_udp_message().await;
-                if let Err(e) = udp_result {
-                    error!(
+                match udp_result {
+                    Ok(()) => self.parent().get_field().metrics().record_udp_packet_sent(),
+                    Err(e) => error!(
                         "Error sending udp message to driver station{}: {}",
                         self.team_number(),
                         e
-                    );
+                    ),
                 };
             }
         }
@@ -212,6 +458,10 @@ impl DriverStation {
 pub struct RawDriverStations {
     field: Option<Field>,
     all_driverstations: Vec<DriverStation>,
+    /// Every open driver station connection, including ones from teams that are not on the
+    /// field
+    connections: Vec<DriverStationConnection>,
+    last_tick_at: Option<Instant>,
 }
 
 #[derive(Clone)]
@@ -256,26 +506,69 @@ impl DriverStations {
             driverstation.team_number(),
             driverstation.alliance_station()
         );
+        drop(raw_driverstations);
+        driverstation.save_assignment();
+        driverstation.notify_changed();
+
+        // Pick up a driver station that was already connected before its team was added
+        if let Some(conn) = self.get_all_connections().into_iter().find(|conn| {
+            conn.team_number() == Some(team_number) && conn.parent().is_none() && conn.is_alive()
+        }) {
+            conn.set_parent(Some(driverstation.clone()));
+            driverstation.set_active_connection(conn);
+            info!("Driver station {} connected", team_number);
+        }
 
         Ok(driverstation)
     }
 
-    pub async fn delete_driverstation(&self, team_number: u16) -> anyhow::Result<()> {
+    /// Removes a driver station from the field. Refused while a match is running, as that
+    /// would drop its E-Stop and A-Stop.
+    pub fn delete_driverstation(&self, team_number: u16) -> anyhow::Result<()> {
+        if self.get_field().match_state().is_running() {
+            bail!("Cannot remove driver station {team_number} while a match is running");
+        }
         let all_driverstations = self.get_all_driverstations();
         let mut new_driverstations: Vec<DriverStation> = Vec::new();
 
+        let mut deleted_station = None;
+
         for ds in all_driverstations.iter() {
             if ds.team_number() != team_number {
                 new_driverstations.push(ds.clone());
-            } else if let Some(conn) = ds.active_connection() {
+            } else {
+                deleted_station = Some(ds.alliance_station());
                 info!("Deleted driverstation {}", team_number);
-                conn.kill().await;
+                // Keep the connection open, so the driver station is told it is no longer in
+                // the match and is picked up again if its team is added back
+                if let Some(conn) = ds.remove_active_connection() {
+                    conn.set_parent(None);
+                    ds.set_confirmed_state(None);
+                    ds.clear_diagnostics();
+                }
             }
         }
 
         if all_driverstations.len() > new_driverstations.len() {
             let mut raw_driverstations = self.raw.write().unwrap();
             raw_driverstations.all_driverstations = new_driverstations;
+            drop(raw_driverstations);
+            let field = self.get_field();
+            if let Some(alliance_station) = deleted_station {
+                field
+                    .metrics()
+                    .remove_driverstation(team_number, alliance_station);
+            }
+            if let Err(e) = field
+                .database()
+                .delete_driverstation_assignment(team_number)
+            {
+                error!(
+                    "Error deleting assignment of driverstation {}: {}",
+                    team_number, e
+                );
+            }
+            field.notify(FieldUpdate::DriverStation { team_number });
             Ok(())
         } else {
             Err(anyhow!(
@@ -315,6 +608,19 @@ impl DriverStations {
         raw_driverstations.all_driverstations.clone()
     }
 
+    /// When the tick loop last finished a tick
+    pub fn last_tick_at(&self) -> Option<Instant> {
+        let raw_driverstations = self.raw.read().unwrap();
+        raw_driverstations.last_tick_at
+    }
+
+    /// Every open driver station connection, including ones from teams that are not on the
+    /// field
+    pub fn get_all_connections(&self) -> Vec<DriverStationConnection> {
+        let raw_driverstations = self.raw.read().unwrap();
+        raw_driverstations.connections.clone()
+    }
+
     pub fn get_field(&self) -> Field {
         let raw_driverstations = self.raw.read().unwrap();
         if let Some(field) = raw_driverstations.field.clone() {
@@ -330,6 +636,8 @@ impl DriverStations {
         let driverstations = RawDriverStations {
             field,
             all_driverstations: Vec::new(),
+            connections: Vec::new(),
+            last_tick_at: None,
         };
 
         Self {
@@ -338,11 +646,16 @@ impl DriverStations {
     }
 
     pub(super) async fn run(self, cancellation_token: CancellationToken) -> anyhow::Result<()> {
-        let task_handle = tokio::task::Builder::new()
+        let tick_loop_handle = tokio::task::Builder::new()
             .name("DriverStations tick loop")
-            .spawn(self.tick_loop(cancellation_token))?;
+            .spawn(self.clone().tick_loop(cancellation_token.clone()))?;
+        let sync_loop_handle = tokio::task::Builder::new()
+            .name("DriverStations TCP sync loop")
+            .spawn(self.sync_loop(cancellation_token))?;
 
-        task_handle.await??;
+        let (tick_loop_result, sync_loop_result) = tokio::join!(tick_loop_handle, sync_loop_handle);
+        tick_loop_result??;
+        sync_loop_result??;
 
         Ok(())
     }
@@ -356,14 +669,25 @@ impl DriverStations {
         Ok(())
     }
 
+    pub(super) fn unregister_connection(&self, uuid: uuid::Uuid) {
+        let mut raw_driverstations = self.raw.write().unwrap();
+        raw_driverstations
+            .connections
+            .retain(|conn| conn.uuid() != uuid);
+    }
+
     async fn tick_loop(self, cancellation_token: CancellationToken) -> anyhow::Result<()> {
         let mut interval = tokio::time::interval(Duration::from_millis(250));
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
         let interval_tick_loop = async {
             loop {
-                interval.tick().await;
+                let due = interval.tick().await;
                 self.tick().await;
+                self.raw.write().unwrap().last_tick_at = Some(Instant::now());
+                self.get_field()
+                    .metrics()
+                    .record_tick_latency("driverstations", due.elapsed());
             }
         };
 
@@ -373,7 +697,36 @@ impl DriverStations {
         }
     }
 
-    async fn tick(&self) {
+    /// Sends every connection the station info, event code and game data it is missing each
+    /// time the field or a driver station changes
+    async fn sync_loop(self, cancellation_token: CancellationToken) -> anyhow::Result<()> {
+        let mut updates = self.get_field().subscribe();
+        loop {
+            tokio::select! {
+                _ = cancellation_token.cancelled() => return Ok(()),
+                update = updates.recv() => match update {
+                    Ok(_) | Err(RecvError::Lagged(_)) => self.sync_tcp_state().await,
+                    Err(RecvError::Closed) => bail!("Field updates closed unexpectedly"),
+                },
+            }
+        }
+    }
+
+    async fn sync_tcp_state(&self) {
+        for conn in self.get_all_connections() {
+            if conn.is_alive()
+                && let Err(e) = conn.sync_tcp_state().await
+            {
+                error!(
+                    "Error updating driver station connection {}: {}",
+                    conn.uuid(),
+                    e
+                );
+            }
+        }
+    }
+
+    pub(super) async fn tick(&self) {
         let all_driverstations = self.get_all_driverstations();
         let field = self.get_field();
         for ds in all_driverstations {
@@ -391,46 +744,59 @@ impl DriverStations {
             }
             ds.tick().await;
         }
-    }
-
-    pub(super) async fn decode_udp_message(&self, buffer: Vec<u8>) -> anyhow::Result<()> {
-        let mut reader = Cursor::new(buffer);
 
-        let _sequence_num = reader.read_u16().await?; //TODO Track Packet loss
-        let _comm_version = reader.read_u8().await?;
-        let status_byte = reader.read_u8().await?;
-        let team_number = reader.read_u16().await?;
-        let battery_byte = reader.read_u16().await?;
-        //TODO Handle Tags
+        for conn in self.get_all_connections() {
+            // Connections without a driver station are not ticked above, so drop them here
+            // once their driver station stops sending UDP
+            if conn.is_alive()
+                && conn.parent().is_none()
+                && Utc::now().signed_duration_since(conn.last_udp_packet_reception()) > UDP_TIMEOUT
+            {
+                conn.kill().await;
+            }
+        }
+    }
 
-        //Status byte info
-        let is_emergency_stopped = (status_byte >> 7 & 0x01) == 1;
-        let robot_communications_active = (status_byte >> 5 & 0x01) == 1;
-        let can_ping_radio = (status_byte >> 4 & 0x01) == 1;
-        let can_ping_rio = (status_byte >> 3 & 0x01) == 1;
-        let is_enabled = (status_byte >> 2 & 0x01) == 1;
-        let mode = Mode::from_byte(status_byte & 0x03);
+    pub(super) fn decode_udp_message(&self, buffer: &[u8]) -> anyhow::Result<()> {
+        self.get_field().metrics().record_udp_packet_received();
 
-        let battery_voltage =
-            f32::from(battery_byte >> 8 & 0xff) + (f32::from(battery_byte & 0xff) / 256.0);
+        let packet = StatusPacket::decode(buffer)?;
+        let team_number = packet.team_number;
+        if let Some(e) = &packet.tag_error {
+            warn!(
+                "Ignoring the rest of the tags of a UDP message from driver station {}: {}",
+                team_number, e
+            );
+        }
 
         let confirmed_state = DriverStationConfirmedState {
-            is_emergency_stopped,
-            robot_communications_active,
-            can_ping_radio,
-            can_ping_rio,
-            is_enabled,
-            mode,
+            is_emergency_stopped: packet.emergency_stopped,
+            robot_communications_active: packet.robot_communications_active,
+            can_ping_radio: packet.can_ping_radio,
+            can_ping_rio: packet.can_ping_rio,
+            is_enabled: packet.enabled,
+            mode: packet.mode,
             team_number,
-            battery_voltage,
+            battery_voltage: packet.battery_voltage,
         };
+        let tags = packet.tags;
+        let sequence_num = packet.sequence_num;
 
         if let Some(ds) = self.get_driverstation_by_team_number(team_number) {
             ds.set_confirmed_state(Some(confirmed_state));
+            if !tags.is_empty() {
+                ds.record_tags(tags);
+            }
             if let Some(active_connection) = ds.active_connection() {
-                active_connection.update_last_udp_packet_reception(Utc::now())
+                active_connection.record_udp_packet(sequence_num, Utc::now())
             }
         } else {
+            // Keep the connection of a driver station waiting to be added alive
+            for conn in self.get_all_connections() {
+                if conn.team_number() == Some(team_number) && conn.parent().is_none() {
+                    conn.record_udp_packet(sequence_num, Utc::now());
+                }
+            }
             warn!(
                 "Received a packet from a driver station that is not in the list of known driver stations. Team Number: {}",
                 team_number
@@ -447,11 +813,21 @@ impl DriverStations {
         cancellation_token: CancellationToken,
     ) -> anyhow::Result<()> {
         let ds_connection = DriverStationConnection::new(ip_address, self.get_field());
-        ds_connection.run(tcp_stream, cancellation_token).await
+        {
+            let mut raw_driverstations = self.raw.write().unwrap();
+            raw_driverstations.connections.push(ds_connection.clone());
+        }
+        let res = ds_connection
+            .clone()
+            .run(tcp_stream, cancellation_token)
+            .await;
+        // `kill` only unregisters connections that got as far as opening their TCP writer
+        self.unregister_connection(ds_connection.uuid());
+        res
     }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq)]
 pub struct DriverStationConfirmedState {
     pub is_emergency_stopped: bool,
     pub robot_communications_active: bool,
@@ -463,6 +839,26 @@ pub struct DriverStationConfirmedState {
     pub battery_voltage: f32,
 }
 
+impl DriverStationConfirmedState {
+    /// Compares two states while ignoring battery voltage changes below 0.1V, which would
+    /// otherwise count as a change on nearly every packet.
+    fn differs_from(&self, other: &DriverStationConfirmedState) -> bool {
+        let round_voltage = |voltage: f32| (voltage * 10.0).round();
+        let mut other = *other;
+        if round_voltage(self.battery_voltage) == round_voltage(other.battery_voltage) {
+            other.battery_voltage = self.battery_voltage;
+        }
+        *self != other
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DriverStationControlEvent {
+    pub control: DriverStationControl,
+    pub triggered_by: String,
+    pub timestamp: u64,
+}
+
 #[derive(Clone, Debug)]
 pub struct DriverStationLogMessage {
     pub timestamp: u64,
@@ -2,7 +2,7 @@ use std::fmt;
 
 // Represents the Mode of a DriverStation. These values correspond to the values you can
 /// get from WPILib and can set on the Driverstation when directly connected.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 #[derive(Default)]
 pub enum Mode {
     #[default]
@@ -46,6 +46,7 @@ impl fmt::Display for Mode {
 /// `DriverstationStatus::Good` when in the correct position, `DriverstationStatus::Bad`
 /// when in the wrong position, and `DriverstationStatus::Waiting` when the team isn't in
 /// this match.
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum DriverstationStatus {
     Good,
     Bad,
@@ -83,7 +84,7 @@ impl fmt::Display for DriverstationStatus {
 
 /// Represents the AllianceStation of a DriverStation. There are six different alliance stations around
 /// an FRC field, three on each side. (Hardcoded due to it's use in the network protocol)
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum AllianceStation {
     Red1,
     Red2,
@@ -143,6 +144,13 @@ pub enum TournamentLevel {
 }
 
 impl TournamentLevel {
+    pub const ALL: [TournamentLevel; 4] = [
+        TournamentLevel::Test,
+        TournamentLevel::Practice,
+        TournamentLevel::Qualification,
+        TournamentLevel::Playoff,
+    ];
+
     pub fn from_byte(integer: u8) -> TournamentLevel {
         match integer {
             0 => TournamentLevel::Test,
@@ -174,7 +182,69 @@ impl fmt::Display for TournamentLevel {
     }
 }
 
-#[derive(Clone, Debug)]
+/// Represents the phase of the match cycle the field is currently in. The field tick loop
+/// advances `Auto` -> `Transition` -> `Teleop` -> `PostMatch` as each period's timer runs out.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MatchState {
+    Idle,
+    PreStart,
+    Auto,
+    Transition,
+    Teleop,
+    PostMatch,
+    Aborted,
+}
+
+impl MatchState {
+    /// Returns `true` while robots are on the field as part of a running match
+    pub fn is_running(self) -> bool {
+        matches!(
+            self,
+            MatchState::Auto | MatchState::Transition | MatchState::Teleop
+        )
+    }
+
+    /// Returns `true` if driver stations should be enabled during this state
+    pub fn is_enabled_period(self) -> bool {
+        matches!(self, MatchState::Auto | MatchState::Teleop)
+    }
+}
+
+impl fmt::Display for MatchState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchState::Idle => write!(f, "Idle"),
+            MatchState::PreStart => write!(f, "PreStart"),
+            MatchState::Auto => write!(f, "Auto"),
+            MatchState::Transition => write!(f, "Transition"),
+            MatchState::Teleop => write!(f, "Teleop"),
+            MatchState::PostMatch => write!(f, "PostMatch"),
+            MatchState::Aborted => write!(f, "Aborted"),
+        }
+    }
+}
+
+/// A manual action taken against a single driver station.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DriverStationControl {
+    Enable,
+    Disable,
+    EmergencyStop,
+    AutonomousStop,
+}
+
+impl fmt::Display for DriverStationControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DriverStationControl::Enable => write!(f, "Enable"),
+            DriverStationControl::Disable => write!(f, "Disable"),
+            DriverStationControl::EmergencyStop => write!(f, "EmergencyStop"),
+            DriverStationControl::AutonomousStop => write!(f, "AutonomousStop"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct VersionData {
     pub version_type: VersionType,
     pub status: String,
@@ -207,6 +277,19 @@ impl VersionType {
             _ => Err(anyhow::anyhow!("Invalid version_type byte: {}", byte)),
         }
     }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            VersionType::WPILib => 0x00,
+            VersionType::RoboRIO => 0x01,
+            VersionType::DS => 0x02,
+            VersionType::PDP => 0x03,
+            VersionType::PCM => 0x04,
+            VersionType::CANJag => 0x05,
+            VersionType::CANTalon => 0x06,
+            VersionType::ThirdParty => 0x07,
+        }
+    }
 }
 
 impl fmt::Display for VersionType {
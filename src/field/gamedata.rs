@@ -0,0 +1,35 @@
+use super::enums::MatchState;
+
+/// The match period from which a driver station is sent its game data. Until then, and again
+/// once the field is idle or a match is aborted, it is sent an empty message.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameDataRelease {
+    PreStart,
+    Auto,
+    Teleop,
+}
+
+impl GameDataRelease {
+    pub fn is_released(self, match_state: MatchState) -> bool {
+        let period = |match_state: MatchState| match match_state {
+            MatchState::Idle | MatchState::Aborted => None,
+            MatchState::PreStart => Some(0),
+            MatchState::Auto | MatchState::Transition => Some(1),
+            MatchState::Teleop | MatchState::PostMatch => Some(2),
+        };
+        let release_period = match self {
+            GameDataRelease::PreStart => 0,
+            GameDataRelease::Auto => 1,
+            GameDataRelease::Teleop => 2,
+        };
+        period(match_state).is_some_and(|period| period >= release_period)
+    }
+}
+
+/// A game-specific message for one alliance station, such as the target some seasons hand
+/// out to each alliance
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameData {
+    pub message: String,
+    pub release: GameDataRelease,
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How far back the rolling packet loss looks
+const LOSS_WINDOW: Duration = Duration::from_secs(10);
+/// A forward jump in sequence numbers larger than this is taken as the driver station
+/// restarting its counter rather than as that many lost packets. Connections are dropped after
+/// two seconds without UDP, which is about 100 packets.
+const MAX_SEQUENCE_GAP: u16 = 500;
+/// Most lost sequence numbers remembered at once, in case they turn up late
+const MAX_MISSING: usize = MAX_SEQUENCE_GAP as usize;
+/// Weight of each new sample in the smoothed jitter, as in RFC 3550
+const JITTER_GAIN: f64 = 1.0 / 16.0;
+
+/// What a received sequence number said about the packets before it
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SequenceEvent {
+    InOrder { lost: u16 },
+    OutOfOrder,
+    Duplicate,
+    Resync,
+}
+
+/// Loss and jitter statistics of the UDP packets received from one driver station, worked out
+/// from their sequence numbers and arrival times.
+#[derive(Clone, Debug, Default)]
+pub struct UdpPacketStats {
+    last_sequence_num: Option<u16>,
+    last_arrival: Option<Instant>,
+    last_interarrival: Option<Duration>,
+    packets_received: u64,
+    packets_lost: u64,
+    packets_out_of_order: u64,
+    packets_duplicated: u64,
+    sequence_wraparounds: u64,
+    sequence_resyncs: u64,
+    jitter_secs: f64,
+    /// Arrival time, packets received and packets lost for each packet in the loss window
+    window: VecDeque<(Instant, u64, u64)>,
+    /// Sequence numbers counted as lost, with the arrival time of the packet that counted them,
+    /// so a late packet can take back its loss once and only once
+    missing: VecDeque<(u16, Instant)>,
+}
+
+impl UdpPacketStats {
+    // Public API -->
+
+    pub fn last_sequence_num(&self) -> Option<u16> {
+        self.last_sequence_num
+    }
+
+    pub fn packets_received(&self) -> u64 {
+        self.packets_received
+    }
+
+    pub fn packets_lost(&self) -> u64 {
+        self.packets_lost
+    }
+
+    pub fn packets_out_of_order(&self) -> u64 {
+        self.packets_out_of_order
+    }
+
+    pub fn packets_duplicated(&self) -> u64 {
+        self.packets_duplicated
+    }
+
+    pub fn sequence_wraparounds(&self) -> u64 {
+        self.sequence_wraparounds
+    }
+
+    /// How many times the sequence numbers jumped too far to be packet loss
+    pub fn sequence_resyncs(&self) -> u64 {
+        self.sequence_resyncs
+    }
+
+    /// The percentage of packets lost over the last ten seconds
+    pub fn recent_packet_loss_percent(&self, now: Instant) -> f64 {
+        let (received, lost) = self
+            .window
+            .iter()
+            .filter(|(arrival, _, _)| now.duration_since(*arrival) <= LOSS_WINDOW)
+            .fold((0, 0), |(received, lost), (_, r, l)| {
+                (received + r, lost + l)
+            });
+        if received + lost == 0 {
+            0.0
+        } else {
+            lost as f64 * 100.0 / (received + lost) as f64
+        }
+    }
+
+    /// Smoothed variation between the times successive packets arrive at, per packet sent
+    pub fn jitter(&self) -> Duration {
+        Duration::from_secs_f64(self.jitter_secs)
+    }
+
+    // Internal API -->
+
+    pub(super) fn record(&mut self, sequence_num: u16, arrival: Instant) {
+        let event = match self.last_sequence_num {
+            None => SequenceEvent::InOrder { lost: 0 },
+            Some(last) => {
+                let gap = sequence_num.wrapping_sub(last);
+                if gap == 0 {
+                    SequenceEvent::Duplicate
+                } else if gap > MAX_SEQUENCE_GAP && gap <= u16::MAX - MAX_SEQUENCE_GAP {
+                    SequenceEvent::Resync
+                } else if gap <= MAX_SEQUENCE_GAP {
+                    SequenceEvent::InOrder { lost: gap - 1 }
+                } else {
+                    SequenceEvent::OutOfOrder
+                }
+            }
+        };
+
+        match event {
+            SequenceEvent::Duplicate => {
+                self.packets_duplicated += 1;
+                return;
+            }
+            SequenceEvent::OutOfOrder => {
+                let Some(index) = self
+                    .missing
+                    .iter()
+                    .position(|(missing, _)| *missing == sequence_num)
+                else {
+                    // Either already received or too old to still be counted as lost
+                    self.packets_duplicated += 1;
+                    return;
+                };
+                // This packet was counted as lost when the ones after it arrived
+                let (_, counted_at) = self.missing.remove(index).unwrap();
+                self.packets_out_of_order += 1;
+                self.packets_lost -= 1;
+                self.packets_received += 1;
+                self.window.push_back((arrival, 1, 0));
+                if let Some(entry) = self
+                    .window
+                    .iter_mut()
+                    .find(|(at, _, lost)| *at == counted_at && *lost > 0)
+                {
+                    entry.2 -= 1;
+                }
+                self.prune_window(arrival);
+                return;
+            }
+            SequenceEvent::Resync => {
+                self.sequence_resyncs += 1;
+                self.missing.clear();
+                self.last_arrival = None;
+                self.last_interarrival = None;
+                self.packets_received += 1;
+                self.window.push_back((arrival, 1, 0));
+            }
+            SequenceEvent::InOrder { lost } => {
+                if self
+                    .last_sequence_num
+                    .is_some_and(|last| sequence_num < last)
+                {
+                    self.sequence_wraparounds += 1;
+                }
+                self.packets_received += 1;
+                self.packets_lost += u64::from(lost);
+                self.window.push_back((arrival, 1, u64::from(lost)));
+                for missing in (1..=lost).map(|behind| sequence_num.wrapping_sub(behind)) {
+                    if self.missing.len() == MAX_MISSING {
+                        self.missing.pop_front();
+                    }
+                    self.missing.push_back((missing, arrival));
+                }
+                self.record_arrival(arrival, u32::from(lost) + 1);
+            }
+        }
+
+        self.last_sequence_num = Some(sequence_num);
+        self.prune_window(arrival);
+    }
+
+    fn record_arrival(&mut self, arrival: Instant, packets_sent: u32) {
+        if let Some(last_arrival) = self.last_arrival {
+            // Spread the time over every packet sent since the last one received, so loss does
+            // not show up as jitter
+            let interarrival = arrival.saturating_duration_since(last_arrival) / packets_sent;
+            if let Some(last_interarrival) = self.last_interarrival {
+                let variation = interarrival.abs_diff(last_interarrival).as_secs_f64();
+                self.jitter_secs += (variation - self.jitter_secs) * JITTER_GAIN;
+            }
+            self.last_interarrival = Some(interarrival);
+        }
+        self.last_arrival = Some(arrival);
+    }
+
+    fn prune_window(&mut self, now: Instant) {
+        while let Some((arrival, _, _)) = self.window.front()
+            && now.duration_since(*arrival) > LOSS_WINDOW
+        {
+            self.window.pop_front();
+        }
+        while let Some((_, counted_at)) = self.missing.front()
+            && now.duration_since(*counted_at) > LOSS_WINDOW
+        {
+            self.missing.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records each sequence number 20ms after the one before it, as a driver station sends them
+    fn record_all(stats: &mut UdpPacketStats, start: Instant, sequence_nums: &[u16]) -> Instant {
+        let mut arrival = start;
+        for &sequence_num in sequence_nums {
+            arrival += Duration::from_millis(20);
+            stats.record(sequence_num, arrival);
+        }
+        arrival
+    }
+
+    #[test]
+    fn in_order_packets_are_not_lost() {
+        let mut stats = UdpPacketStats::default();
+        let now = record_all(&mut stats, Instant::now(), &[1, 2, 3, 4]);
+        assert_eq!(stats.packets_received(), 4);
+        assert_eq!(stats.packets_lost(), 0);
+        assert_eq!(stats.last_sequence_num(), Some(4));
+        assert_eq!(stats.recent_packet_loss_percent(now), 0.0);
+    }
+
+    #[test]
+    fn gap_counts_skipped_packets_as_lost() {
+        let mut stats = UdpPacketStats::default();
+        let now = record_all(&mut stats, Instant::now(), &[1, 2, 5, 6]);
+        assert_eq!(stats.packets_received(), 4);
+        assert_eq!(stats.packets_lost(), 2);
+        assert!((stats.recent_packet_loss_percent(now) - 100.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sequence_wraps_around_without_loss() {
+        let mut stats = UdpPacketStats::default();
+        record_all(&mut stats, Instant::now(), &[65534, 65535, 0, 1]);
+        assert_eq!(stats.sequence_wraparounds(), 1);
+        assert_eq!(stats.packets_lost(), 0);
+
+        let mut stats = UdpPacketStats::default();
+        record_all(&mut stats, Instant::now(), &[65534, 0]);
+        assert_eq!(stats.sequence_wraparounds(), 1);
+        assert_eq!(stats.packets_lost(), 1);
+    }
+
+    #[test]
+    fn large_jump_resyncs_instead_of_counting_loss() {
+        let mut stats = UdpPacketStats::default();
+        record_all(
+            &mut stats,
+            Instant::now(),
+            &[10, 11, 11 + MAX_SEQUENCE_GAP + 1, 0],
+        );
+        assert_eq!(stats.sequence_resyncs(), 2);
+        assert_eq!(stats.packets_lost(), 0);
+        assert_eq!(stats.last_sequence_num(), Some(0));
+    }
+
+    #[test]
+    fn late_packet_takes_back_its_loss_once() {
+        let mut stats = UdpPacketStats::default();
+        let now = record_all(&mut stats, Instant::now(), &[1, 2, 4, 3, 3, 5]);
+        assert_eq!(stats.packets_received(), 5);
+        assert_eq!(stats.packets_lost(), 0);
+        assert_eq!(stats.packets_out_of_order(), 1);
+        assert_eq!(stats.packets_duplicated(), 1);
+        assert_eq!(stats.recent_packet_loss_percent(now), 0.0);
+    }
+
+    #[test]
+    fn late_duplicate_of_a_received_packet_does_not_hide_loss() {
+        let mut stats = UdpPacketStats::default();
+        record_all(&mut stats, Instant::now(), &[1, 2, 3, 5, 2]);
+        assert_eq!(stats.packets_lost(), 1);
+        assert_eq!(stats.packets_out_of_order(), 0);
+        assert_eq!(stats.packets_duplicated(), 1);
+    }
+
+    #[test]
+    fn loss_leaves_the_window_after_ten_seconds() {
+        let mut stats = UdpPacketStats::default();
+        let start = Instant::now();
+        let now = record_all(&mut stats, start, &[1, 3]);
+        assert!(stats.recent_packet_loss_percent(now) > 0.0);
+
+        let later = now + LOSS_WINDOW + Duration::from_millis(20);
+        stats.record(4, later);
+        assert_eq!(stats.recent_packet_loss_percent(later), 0.0);
+        assert_eq!(stats.packets_lost(), 1);
+
+        // Too late to count as the lost packet arriving
+        stats.record(2, later);
+        assert_eq!(stats.packets_lost(), 1);
+        assert_eq!(stats.packets_duplicated(), 1);
+    }
+
+    #[test]
+    fn steady_packets_have_no_jitter() {
+        let mut stats = UdpPacketStats::default();
+        record_all(&mut stats, Instant::now(), &[1, 2, 3, 4, 5]);
+        assert_eq!(stats.jitter(), Duration::ZERO);
+    }
+
+    #[test]
+    fn lost_packets_do_not_show_up_as_jitter() {
+        let mut stats = UdpPacketStats::default();
+        let start = Instant::now();
+        stats.record(1, start);
+        stats.record(2, start + Duration::from_millis(20));
+        stats.record(4, start + Duration::from_millis(60));
+        stats.record(5, start + Duration::from_millis(80));
+        assert_eq!(stats.jitter(), Duration::ZERO);
+    }
+
+    #[test]
+    fn uneven_arrivals_build_up_jitter() {
+        let mut stats = UdpPacketStats::default();
+        let start = Instant::now();
+        stats.record(1, start);
+        stats.record(2, start + Duration::from_millis(10));
+        stats.record(3, start + Duration::from_millis(40));
+        // |30ms - 10ms| weighted by the RFC 3550 gain
+        let expected = 0.020 * JITTER_GAIN;
+        assert!((stats.jitter().as_secs_f64() - expected).abs() < 1e-9);
+    }
+}
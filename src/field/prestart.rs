@@ -0,0 +1,164 @@
+use std::fmt;
+
+use super::{
+    Field,
+    enums::{AllianceStation, DriverstationStatus},
+};
+
+/// A single condition that keeps the field from being ready to start a match.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PreStartFailure {
+    FieldSafe,
+    FieldFaulted,
+    NotConnected,
+    NoRobotComms,
+    NoRioPing,
+    WrongStation,
+    StationFaulted,
+}
+
+impl fmt::Display for PreStartFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PreStartFailure::FieldSafe => write!(f, "Field is still flagged as safe"),
+            PreStartFailure::FieldFaulted => write!(f, "Field has an active fault"),
+            PreStartFailure::NotConnected => write!(f, "Driver station is not connected"),
+            PreStartFailure::NoRobotComms => write!(f, "No communication with the robot"),
+            PreStartFailure::NoRioPing => write!(f, "Driver station cannot ping the roboRIO"),
+            PreStartFailure::WrongStation => {
+                write!(f, "Driver station is connected from the wrong station")
+            }
+            PreStartFailure::StationFaulted => write!(f, "Driver station has an active fault"),
+        }
+    }
+}
+
+impl PreStartFailure {
+    /// Whether a match may be started with this failure when the prestart check is
+    /// overridden. Starting on a safe or faulted field would abort the match straight away.
+    pub fn can_override(self) -> bool {
+        !matches!(
+            self,
+            PreStartFailure::FieldSafe | PreStartFailure::FieldFaulted
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PreStartStationCheck {
+    pub alliance_station: AllianceStation,
+    /// `None` when no driver station is assigned to this alliance station
+    pub team_number: Option<u16>,
+    pub failures: Vec<PreStartFailure>,
+}
+
+/// The result of checking every alliance station and the field itself before a match starts.
+#[derive(Clone, Debug)]
+pub struct PreStartCheck {
+    pub field_failures: Vec<PreStartFailure>,
+    pub stations: Vec<PreStartStationCheck>,
+}
+
+impl PreStartCheck {
+    pub fn is_ready(&self) -> bool {
+        self.field_failures.is_empty()
+            && self
+                .stations
+                .iter()
+                .all(|station| station.failures.is_empty())
+    }
+
+    /// Whether every failure of this check may be overridden
+    pub fn can_override(&self) -> bool {
+        self.field_failures
+            .iter()
+            .chain(
+                self.stations
+                    .iter()
+                    .flat_map(|station| station.failures.iter()),
+            )
+            .all(|failure| failure.can_override())
+    }
+
+    pub(super) fn run(field: &Field) -> Self {
+        let alarm_handler = field.alarm_handler();
+
+        let mut field_failures = Vec::new();
+        if field.is_safe() {
+            field_failures.push(PreStartFailure::FieldSafe);
+        }
+        if alarm_handler.is_target_faulted(field.alarm_target().as_str()) {
+            field_failures.push(PreStartFailure::FieldFaulted);
+        }
+
+        let driverstations = field.driverstations();
+        let stations = [
+            AllianceStation::Red1,
+            AllianceStation::Red2,
+            AllianceStation::Red3,
+            AllianceStation::Blue1,
+            AllianceStation::Blue2,
+            AllianceStation::Blue3,
+        ]
+        .into_iter()
+        .map(|alliance_station| {
+            let Some(ds) = driverstations.get_driverstation_by_position(alliance_station) else {
+                return PreStartStationCheck {
+                    alliance_station,
+                    team_number: None,
+                    failures: Vec::new(),
+                };
+            };
+
+            let mut failures = Vec::new();
+            match ds.active_connection() {
+                Some(conn) if conn.is_alive() => {
+                    if let DriverstationStatus::Bad = conn.station_status() {
+                        failures.push(PreStartFailure::WrongStation);
+                    }
+                }
+                _ => failures.push(PreStartFailure::NotConnected),
+            }
+
+            let confirmed_state = ds.confirmed_state().unwrap_or_default();
+            if !confirmed_state.robot_communications_active {
+                failures.push(PreStartFailure::NoRobotComms);
+            }
+            if !confirmed_state.can_ping_rio {
+                failures.push(PreStartFailure::NoRioPing);
+            }
+
+            if alarm_handler.is_target_faulted(ds.alarm_target().as_str()) {
+                failures.push(PreStartFailure::StationFaulted);
+            }
+
+            PreStartStationCheck {
+                alliance_station,
+                team_number: Some(ds.team_number()),
+                failures,
+            }
+        })
+        .collect();
+
+        Self {
+            field_failures,
+            stations,
+        }
+    }
+}
+
+impl fmt::Display for PreStartCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut failures: Vec<String> = self
+            .field_failures
+            .iter()
+            .map(|failure| failure.to_string())
+            .collect();
+        for station in self.stations.iter() {
+            for failure in station.failures.iter() {
+                failures.push(format!("{}: {}", station.alliance_station, failure));
+            }
+        }
+        write!(f, "{}", failures.join(", "))
+    }
+}
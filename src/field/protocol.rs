@@ -0,0 +1,1206 @@
+//! Encoding and decoding of every packet exchanged with driver stations, kept apart from the
+//! sockets so it can be tested and reused on its own.
+//!
+//! Driver stations send `StatusPacket`s to the FMS over UDP port 1160 and receive
+//! `ControlPacket`s on UDP port 1121. Over TCP port 1750 every packet is framed by a two byte
+//! length followed by a one byte id, see `frame`.
+
+use std::fmt;
+
+use chrono::{DateTime, Datelike, TimeZone, Timelike};
+
+use super::enums::{
+    AllianceStation, DriverstationStatus, Mode, TournamentLevel, VersionData, VersionType,
+};
+
+/// Seconds between the LabVIEW epoch (1904) that log message timestamps count from and the
+/// UNIX epoch
+const LABVIEW_EPOCH_OFFSET: u64 = 2082844800;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProtocolError {
+    /// The packet ended before the field being read
+    Truncated {
+        packet: &'static str,
+        needed: usize,
+        remaining: usize,
+    },
+    /// A TCP frame without even a packet id
+    EmptyFrame,
+    /// A TCP frame longer than its two byte length prefix can describe
+    FrameTooLong(usize),
+    /// A tag that claims to be longer than what is left of the packet
+    TagOverrun {
+        size: usize,
+        remaining: usize,
+    },
+    InvalidValue {
+        field: &'static str,
+        value: u64,
+    },
+    InvalidUtf8 {
+        packet: &'static str,
+    },
+    /// A string longer than its one byte length prefix can describe
+    StringTooLong {
+        field: &'static str,
+        length: usize,
+    },
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProtocolError::Truncated {
+                packet,
+                needed,
+                remaining,
+            } => write!(
+                f,
+                "{} packet is truncated, needed {} more bytes but only {} are left",
+                packet, needed, remaining
+            ),
+            ProtocolError::EmptyFrame => write!(f, "TCP frame has no packet id"),
+            ProtocolError::FrameTooLong(length) => {
+                write!(f, "TCP frame of {} bytes does not fit its length", length)
+            }
+            ProtocolError::TagOverrun { size, remaining } => write!(
+                f,
+                "Tag of {} bytes runs past the end of the packet ({} bytes left)",
+                size, remaining
+            ),
+            ProtocolError::InvalidValue { field, value } => {
+                write!(f, "Invalid {}: {}", field, value)
+            }
+            ProtocolError::InvalidUtf8 { packet } => {
+                write!(f, "{} packet contains invalid UTF-8", packet)
+            }
+            ProtocolError::StringTooLong { field, length } => write!(
+                f,
+                "{} cannot be longer than {} bytes, got {}",
+                field,
+                u8::MAX,
+                length
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+// UDP -->
+
+/// The wall clock time the FMS sends to driver stations in every control packet
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PacketTime {
+    pub microsecond: u32,
+    pub second: u8,
+    pub minute: u8,
+    pub hour: u8,
+    pub day: u8,
+    pub month: u8,
+    pub year: u16,
+}
+
+impl PacketTime {
+    pub fn from_datetime<Tz: TimeZone>(time: &DateTime<Tz>) -> Self {
+        Self {
+            microsecond: time.nanosecond() / 1000,
+            second: time.second() as u8,
+            minute: time.minute() as u8,
+            hour: time.hour() as u8,
+            day: time.day() as u8,
+            month: time.month() as u8,
+            year: time.year().clamp(1900, 1900 + u8::MAX as i32) as u16,
+        }
+    }
+}
+
+/// Sent by the FMS to a driver station to tell it how to run its robot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ControlPacket {
+    pub sequence_num: u16,
+    pub comm_version: u8,
+    pub emergency_stop: bool,
+    pub enabled: bool,
+    pub mode: Mode,
+    pub request: u8,
+    pub alliance_station: AllianceStation,
+    pub tournament_level: TournamentLevel,
+    pub match_number: u16,
+    pub play_number: u8,
+    pub time: PacketTime,
+    /// Seconds left in the current period
+    pub time_remaining: u16,
+}
+
+impl ControlPacket {
+    const NAME: &'static str = "Control";
+
+    pub fn encode(&self) -> Result<Vec<u8>, ProtocolError> {
+        let mut control_byte = self.mode.to_byte();
+        if self.enabled {
+            control_byte |= 0x04;
+        }
+        if self.emergency_stop {
+            control_byte |= 0x80;
+        }
+        let year = self
+            .time
+            .year
+            .checked_sub(1900)
+            .and_then(|year| u8::try_from(year).ok())
+            .ok_or(ProtocolError::InvalidValue {
+                field: "year",
+                value: self.time.year.into(),
+            })?;
+
+        let mut packet = Vec::with_capacity(22);
+        packet.extend_from_slice(&self.sequence_num.to_be_bytes());
+        packet.push(self.comm_version);
+        packet.push(control_byte);
+        packet.push(self.request);
+        packet.push(self.alliance_station.to_byte());
+        packet.push(self.tournament_level.to_byte());
+        packet.extend_from_slice(&self.match_number.to_be_bytes());
+        packet.push(self.play_number);
+        packet.extend_from_slice(&self.time.microsecond.to_be_bytes());
+        packet.push(self.time.second);
+        packet.push(self.time.minute);
+        packet.push(self.time.hour);
+        packet.push(self.time.day);
+        packet.push(self.time.month);
+        packet.push(year);
+        packet.extend_from_slice(&self.time_remaining.to_be_bytes());
+        Ok(packet)
+    }
+
+    pub fn decode(buffer: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = Reader::new(Self::NAME, buffer);
+        let sequence_num = reader.u16()?;
+        let comm_version = reader.u8()?;
+        let control_byte = reader.u8()?;
+        let request = reader.u8()?;
+        let alliance_station = alliance_station_from_byte(reader.u8()?)?;
+        let tournament_level = tournament_level_from_byte(reader.u8()?)?;
+        let match_number = reader.u16()?;
+        let play_number = reader.u8()?;
+        let time = PacketTime {
+            microsecond: reader.u32()?,
+            second: reader.u8()?,
+            minute: reader.u8()?,
+            hour: reader.u8()?,
+            day: reader.u8()?,
+            month: reader.u8()?,
+            year: 1900 + u16::from(reader.u8()?),
+        };
+        let time_remaining = reader.u16()?;
+
+        Ok(Self {
+            sequence_num,
+            comm_version,
+            emergency_stop: control_byte & 0x80 != 0,
+            enabled: control_byte & 0x04 != 0,
+            mode: Mode::from_byte(control_byte & 0x03),
+            request,
+            alliance_station,
+            tournament_level,
+            match_number,
+            play_number,
+            time,
+            time_remaining,
+        })
+    }
+}
+
+/// Sent by a driver station to the FMS with the state of itself and its robot
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusPacket {
+    pub sequence_num: u16,
+    pub comm_version: u8,
+    pub emergency_stopped: bool,
+    pub robot_communications_active: bool,
+    pub can_ping_radio: bool,
+    pub can_ping_rio: bool,
+    pub enabled: bool,
+    pub mode: Mode,
+    pub team_number: u16,
+    pub battery_voltage: f32,
+    pub tags: Vec<DriverStationTag>,
+    /// Set when the tagged section ended in a tag that could not be read. The tags before it
+    /// are still in `tags`.
+    pub tag_error: Option<ProtocolError>,
+}
+
+impl StatusPacket {
+    const NAME: &'static str = "Status";
+
+    pub fn encode(&self) -> Result<Vec<u8>, ProtocolError> {
+        let mut status_byte = self.mode.to_byte();
+        for (set, bit) in [
+            (self.emergency_stopped, 7),
+            (self.robot_communications_active, 5),
+            (self.can_ping_radio, 4),
+            (self.can_ping_rio, 3),
+            (self.enabled, 2),
+        ] {
+            if set {
+                status_byte |= 1 << bit;
+            }
+        }
+
+        let mut packet = Vec::with_capacity(8);
+        packet.extend_from_slice(&self.sequence_num.to_be_bytes());
+        packet.push(self.comm_version);
+        packet.push(status_byte);
+        packet.extend_from_slice(&self.team_number.to_be_bytes());
+        packet.extend_from_slice(&encode_voltage(self.battery_voltage));
+        for tag in self.tags.iter() {
+            tag.encode_into(&mut packet)?;
+        }
+        Ok(packet)
+    }
+
+    pub fn decode(buffer: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = Reader::new(Self::NAME, buffer);
+        let sequence_num = reader.u16()?;
+        let comm_version = reader.u8()?;
+        let status_byte = reader.u8()?;
+        let team_number = reader.u16()?;
+        let battery_voltage = decode_voltage(reader.u16()?);
+
+        let mut tags = Vec::new();
+        let tag_error = DriverStationTag::decode_all(reader.rest(), &mut tags).err();
+
+        Ok(Self {
+            sequence_num,
+            comm_version,
+            emergency_stopped: status_byte >> 7 & 0x01 == 1,
+            robot_communications_active: status_byte >> 5 & 0x01 == 1,
+            can_ping_radio: status_byte >> 4 & 0x01 == 1,
+            can_ping_rio: status_byte >> 3 & 0x01 == 1,
+            enabled: status_byte >> 2 & 0x01 == 1,
+            mode: Mode::from_byte(status_byte & 0x03),
+            team_number,
+            battery_voltage,
+            tags,
+            tag_error,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RadioMetrics {
+    pub signal_strength: u8,
+    pub bandwidth_utilization: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CommsMetrics {
+    pub lost_packets: u16,
+    pub sent_packets: u16,
+    pub average_trip_time: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LaptopMetrics {
+    pub battery_percent: u8,
+    pub cpu_percent: u8,
+}
+
+/// One entry of the tagged section a driver station appends to its status packets. Every
+/// tag is a length byte covering the id and the data, the id and then the data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DriverStationTag {
+    FieldRadioMetrics(RadioMetrics),
+    CommsMetrics(CommsMetrics),
+    LaptopMetrics(LaptopMetrics),
+    RobotRadioMetrics(RadioMetrics),
+    /// A tag this FMS does not know how to decode, or a known one with an unexpected length
+    Unknown {
+        id: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl DriverStationTag {
+    const NAME: &'static str = "Tag";
+
+    /// Decodes tags into `tags` until the end of `buffer`. Fails on a tag that claims to be
+    /// longer than what is left, but never on its contents, and leaves the tags decoded
+    /// before the bad one in `tags`.
+    pub fn decode_all(buffer: &[u8], tags: &mut Vec<Self>) -> Result<(), ProtocolError> {
+        let mut reader = Reader::new(Self::NAME, buffer);
+        while !reader.is_empty() {
+            let size = reader.u8()? as usize;
+            if size == 0 {
+                continue;
+            }
+            if size > reader.remaining() {
+                return Err(ProtocolError::TagOverrun {
+                    size,
+                    remaining: reader.remaining(),
+                });
+            }
+            let id = reader.u8()?;
+            let data = reader.bytes(size - 1)?;
+            tags.push(Self::decode(id, data));
+        }
+        Ok(())
+    }
+
+    fn decode(id: u8, data: &[u8]) -> Self {
+        let word = |index: usize| u16::from_be_bytes([data[index], data[index + 1]]);
+        match (id, data.len()) {
+            (0x00, 3) => Self::FieldRadioMetrics(RadioMetrics {
+                signal_strength: data[0],
+                bandwidth_utilization: word(1),
+            }),
+            (0x01, 5) => Self::CommsMetrics(CommsMetrics {
+                lost_packets: word(0),
+                sent_packets: word(2),
+                average_trip_time: data[4],
+            }),
+            (0x02, 2) => Self::LaptopMetrics(LaptopMetrics {
+                battery_percent: data[0],
+                cpu_percent: data[1],
+            }),
+            (0x03, 3) => Self::RobotRadioMetrics(RadioMetrics {
+                signal_strength: data[0],
+                bandwidth_utilization: word(1),
+            }),
+            _ => Self::Unknown {
+                id,
+                data: data.to_vec(),
+            },
+        }
+    }
+
+    fn encode_into(&self, packet: &mut Vec<u8>) -> Result<(), ProtocolError> {
+        let (id, data) = match self {
+            Self::FieldRadioMetrics(metrics) => (0x00, encode_radio_metrics(metrics)),
+            Self::CommsMetrics(metrics) => {
+                let mut data = Vec::with_capacity(5);
+                data.extend_from_slice(&metrics.lost_packets.to_be_bytes());
+                data.extend_from_slice(&metrics.sent_packets.to_be_bytes());
+                data.push(metrics.average_trip_time);
+                (0x01, data)
+            }
+            Self::LaptopMetrics(metrics) => {
+                (0x02, vec![metrics.battery_percent, metrics.cpu_percent])
+            }
+            Self::RobotRadioMetrics(metrics) => (0x03, encode_radio_metrics(metrics)),
+            Self::Unknown { id, data } => (*id, data.clone()),
+        };
+        let size = u8::try_from(data.len() + 1).map_err(|_| ProtocolError::StringTooLong {
+            field: "Tag data",
+            length: data.len(),
+        })?;
+        packet.push(size);
+        packet.push(id);
+        packet.extend_from_slice(&data);
+        Ok(())
+    }
+}
+
+fn encode_radio_metrics(metrics: &RadioMetrics) -> Vec<u8> {
+    let mut data = vec![metrics.signal_strength];
+    data.extend_from_slice(&metrics.bandwidth_utilization.to_be_bytes());
+    data
+}
+
+// TCP -->
+
+/// Adds the two byte length prefix to an encoded TCP packet
+pub fn frame(packet: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+    let length =
+        u16::try_from(packet.len()).map_err(|_| ProtocolError::FrameTooLong(packet.len()))?;
+    let mut framed = Vec::with_capacity(packet.len() + 2);
+    framed.extend_from_slice(&length.to_be_bytes());
+    framed.extend_from_slice(packet);
+    Ok(framed)
+}
+
+/// The length of the TCP packet following a two byte length prefix
+pub fn frame_length(prefix: [u8; 2]) -> usize {
+    u16::from_be_bytes(prefix) as usize
+}
+
+/// TCP packets sent by the FMS to a driver station
+#[derive(Clone, Debug, PartialEq)]
+pub enum FmsTcpPacket {
+    EventCode(String),
+    StationInfo {
+        alliance_station: AllianceStation,
+        status: DriverstationStatus,
+    },
+    GameData(String),
+}
+
+impl FmsTcpPacket {
+    const NAME: &'static str = "FMS TCP";
+
+    /// Encodes the packet without its length prefix
+    pub fn encode(&self) -> Result<Vec<u8>, ProtocolError> {
+        let mut packet = Vec::new();
+        match self {
+            FmsTcpPacket::EventCode(event_code) => {
+                packet.push(0x14);
+                write_short_string(&mut packet, "Event code", event_code)?;
+            }
+            FmsTcpPacket::StationInfo {
+                alliance_station,
+                status,
+            } => {
+                packet.push(0x19);
+                packet.push(alliance_station.to_byte());
+                packet.push(status.to_byte());
+            }
+            FmsTcpPacket::GameData(game_data) => {
+                packet.push(0x1c);
+                write_short_string(&mut packet, "Game data", game_data)?;
+            }
+        }
+        Ok(packet)
+    }
+
+    /// Encodes the packet with its length prefix, ready to be written to the stream
+    pub fn encode_framed(&self) -> Result<Vec<u8>, ProtocolError> {
+        frame(&self.encode()?)
+    }
+
+    /// Decodes a packet without its length prefix
+    pub fn decode(packet: &[u8]) -> Result<Self, ProtocolError> {
+        let (id, data) = packet.split_first().ok_or(ProtocolError::EmptyFrame)?;
+        let mut reader = Reader::new(Self::NAME, data);
+        let packet = match id {
+            0x14 => FmsTcpPacket::EventCode(reader.short_string()?),
+            0x19 => FmsTcpPacket::StationInfo {
+                alliance_station: alliance_station_from_byte(reader.u8()?)?,
+                status: driverstation_status_from_byte(reader.u8()?)?,
+            },
+            0x1c => FmsTcpPacket::GameData(reader.short_string()?),
+            unknown_id => {
+                return Err(ProtocolError::InvalidValue {
+                    field: "FMS TCP packet id",
+                    value: (*unknown_id).into(),
+                });
+            }
+        };
+        Ok(packet)
+    }
+}
+
+/// A log data packet, with the values converted from their wire units
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogDataPacket {
+    /// Round trip time between driver station and robot in milliseconds
+    pub trip_time: u8,
+    pub lost_packets: u8,
+    pub voltage: f32,
+    pub brownout: bool,
+    pub watchdog: bool,
+    pub ds_teleop: bool,
+    pub ds_auto: bool,
+    pub ds_disable: bool,
+    pub robot_teleop: bool,
+    pub robot_auto: bool,
+    pub robot_disable: bool,
+    /// CAN bus utilization in percent
+    pub can_utilization: u8,
+    /// Radio signal strength in percent
+    pub signal: u8,
+    /// Radio bandwidth in megabits per second
+    pub bandwidth: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogMessagePacket {
+    pub message_count: u32,
+    /// UNIX timestamp in seconds of the driver station clock
+    pub timestamp: u64,
+    pub timestamp_fraction: u64,
+    pub sequence_num: u32,
+    pub message: String,
+}
+
+/// TCP packets sent by a driver station to the FMS
+#[derive(Clone, Debug, PartialEq)]
+pub enum DsTcpPacket {
+    Version(VersionData),
+    LogData(LogDataPacket),
+    LogMessage(LogMessagePacket),
+    TeamNumber(u16),
+    KeepAlive,
+    /// A packet this FMS does not know how to decode
+    Unknown {
+        id: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl DsTcpPacket {
+    const NAME: &'static str = "DS TCP";
+
+    /// Encodes the packet without its length prefix
+    pub fn encode(&self) -> Result<Vec<u8>, ProtocolError> {
+        let mut packet = Vec::new();
+        match self {
+            DsTcpPacket::Version(version) => {
+                packet.push(version.version_type.to_byte());
+                packet.extend_from_slice(
+                    format!("<{}>{}", version.status, version.version).as_bytes(),
+                );
+            }
+            DsTcpPacket::LogData(log_data) => {
+                packet.push(0x16);
+                packet.push(double_unit(log_data.trip_time, "trip time")?);
+                packet.push(log_data.lost_packets);
+                packet.extend_from_slice(&encode_voltage(log_data.voltage));
+                let mut status_byte = 0;
+                for (set, bit) in [
+                    (log_data.brownout, 7),
+                    (log_data.watchdog, 6),
+                    (log_data.ds_teleop, 5),
+                    (log_data.ds_auto, 4),
+                    (log_data.ds_disable, 3),
+                    (log_data.robot_teleop, 2),
+                    (log_data.robot_auto, 1),
+                    (log_data.robot_disable, 0),
+                ] {
+                    if set {
+                        status_byte |= 1 << bit;
+                    }
+                }
+                packet.push(status_byte);
+                packet.push(double_unit(log_data.can_utilization, "CAN utilization")?);
+                packet.push(double_unit(log_data.signal, "signal")?);
+                let bandwidth = (log_data.bandwidth * 256.0)
+                    .round()
+                    .clamp(0.0, u16::MAX.into());
+                packet.extend_from_slice(&(bandwidth as u16).to_be_bytes());
+            }
+            DsTcpPacket::LogMessage(log_message) => {
+                packet.push(0x17);
+                packet.extend_from_slice(&log_message.message_count.to_be_bytes());
+                let timestamp = log_message.timestamp + LABVIEW_EPOCH_OFFSET;
+                packet.extend_from_slice(&timestamp.to_be_bytes());
+                packet.extend_from_slice(&log_message.timestamp_fraction.to_be_bytes());
+                packet.extend_from_slice(&log_message.sequence_num.to_be_bytes());
+                packet.extend_from_slice(log_message.message.as_bytes());
+            }
+            DsTcpPacket::TeamNumber(team_number) => {
+                packet.push(0x18);
+                packet.extend_from_slice(&team_number.to_be_bytes());
+            }
+            DsTcpPacket::KeepAlive => packet.push(0x1d),
+            DsTcpPacket::Unknown { id, data } => {
+                packet.push(*id);
+                packet.extend_from_slice(data);
+            }
+        }
+        Ok(packet)
+    }
+
+    /// Encodes the packet with its length prefix, ready to be written to the stream
+    pub fn encode_framed(&self) -> Result<Vec<u8>, ProtocolError> {
+        frame(&self.encode()?)
+    }
+
+    /// Decodes a packet without its length prefix
+    pub fn decode(packet: &[u8]) -> Result<Self, ProtocolError> {
+        let (id, data) = packet.split_first().ok_or(ProtocolError::EmptyFrame)?;
+        let mut reader = Reader::new(Self::NAME, data);
+        let packet = match *id {
+            0x00..=0x07 => {
+                let version_unparsed = std::str::from_utf8(reader.rest())
+                    .map_err(|_| ProtocolError::InvalidUtf8 { packet: "Version" })?;
+                let (status, version) = match version_unparsed.split_once('>') {
+                    Some((status, version)) => (
+                        status.trim_start_matches('<').to_string(),
+                        version.to_string(),
+                    ),
+                    None => (String::new(), String::new()),
+                };
+                DsTcpPacket::Version(VersionData {
+                    version_type: version_type_from_byte(*id)?,
+                    status,
+                    version,
+                })
+            }
+            0x16 => {
+                let trip_time = reader.u8()? / 2;
+                let lost_packets = reader.u8()?;
+                let voltage = decode_voltage(reader.u16()?);
+                let status_byte = reader.u8()?;
+                let can_utilization = reader.u8()? / 2;
+                let signal = reader.u8()? / 2;
+                let bandwidth = reader.u16()? as f32 / 256.0;
+                DsTcpPacket::LogData(LogDataPacket {
+                    trip_time,
+                    lost_packets,
+                    voltage,
+                    brownout: status_byte >> 7 & 0x01 == 1,
+                    watchdog: status_byte >> 6 & 0x01 == 1,
+                    ds_teleop: status_byte >> 5 & 0x01 == 1,
+                    ds_auto: status_byte >> 4 & 0x01 == 1,
+                    ds_disable: status_byte >> 3 & 0x01 == 1,
+                    robot_teleop: status_byte >> 2 & 0x01 == 1,
+                    robot_auto: status_byte >> 1 & 0x01 == 1,
+                    robot_disable: status_byte & 0x01 == 1,
+                    can_utilization,
+                    signal,
+                    bandwidth,
+                })
+            }
+            0x17 => {
+                let message_count = reader.u32()?;
+                let labview_timestamp = reader.u64()?;
+                let timestamp = labview_timestamp.checked_sub(LABVIEW_EPOCH_OFFSET).ok_or(
+                    ProtocolError::InvalidValue {
+                        field: "log message timestamp",
+                        value: labview_timestamp,
+                    },
+                )?;
+                let timestamp_fraction = reader.u64()?;
+                let sequence_num = reader.u32()?;
+                // Robot code can log arbitrary bytes, so keep the message rather than dropping it
+                let message = String::from_utf8_lossy(reader.rest()).into_owned();
+                DsTcpPacket::LogMessage(LogMessagePacket {
+                    message_count,
+                    timestamp,
+                    timestamp_fraction,
+                    sequence_num,
+                    message,
+                })
+            }
+            0x18 => DsTcpPacket::TeamNumber(reader.u16()?),
+            0x1d => DsTcpPacket::KeepAlive,
+            unknown_id => DsTcpPacket::Unknown {
+                id: unknown_id,
+                data: data.to_vec(),
+            },
+        };
+        Ok(packet)
+    }
+}
+
+// Helpers -->
+
+/// Reads big endian values from a packet, failing with `ProtocolError::Truncated` instead of
+/// running past its end
+struct Reader<'a> {
+    packet: &'static str,
+    buffer: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(packet: &'static str, buffer: &'a [u8]) -> Self {
+        Self { packet, buffer }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    fn remaining(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], ProtocolError> {
+        if length > self.buffer.len() {
+            return Err(ProtocolError::Truncated {
+                packet: self.packet,
+                needed: length,
+                remaining: self.buffer.len(),
+            });
+        }
+        let (bytes, rest) = self.buffer.split_at(length);
+        self.buffer = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ProtocolError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, ProtocolError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ProtocolError> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, ProtocolError> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, ProtocolError> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    /// A string preceded by a one byte length
+    fn short_string(&mut self) -> Result<String, ProtocolError> {
+        let length = self.u8()? as usize;
+        let bytes = self.bytes(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| ProtocolError::InvalidUtf8 {
+            packet: self.packet,
+        })
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+fn write_short_string(
+    packet: &mut Vec<u8>,
+    field: &'static str,
+    value: &str,
+) -> Result<(), ProtocolError> {
+    let length = u8::try_from(value.len()).map_err(|_| ProtocolError::StringTooLong {
+        field,
+        length: value.len(),
+    })?;
+    packet.push(length);
+    packet.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+/// Voltages are sent as a whole volts byte followed by a 1/256 volts byte
+fn decode_voltage(voltage: u16) -> f32 {
+    f32::from(voltage >> 8 & 0xff) + (f32::from(voltage & 0xff) / 256.0)
+}
+
+fn encode_voltage(voltage: f32) -> [u8; 2] {
+    let voltage = (voltage * 256.0).round().clamp(0.0, u16::MAX.into()) as u16;
+    voltage.to_be_bytes()
+}
+
+/// Log data sends some values in half units
+fn double_unit(value: u8, field: &'static str) -> Result<u8, ProtocolError> {
+    value.checked_mul(2).ok_or(ProtocolError::InvalidValue {
+        field,
+        value: value.into(),
+    })
+}
+
+fn alliance_station_from_byte(byte: u8) -> Result<AllianceStation, ProtocolError> {
+    match byte {
+        0..=5 => Ok(AllianceStation::from_byte(byte)),
+        _ => Err(ProtocolError::InvalidValue {
+            field: "alliance station",
+            value: byte.into(),
+        }),
+    }
+}
+
+fn tournament_level_from_byte(byte: u8) -> Result<TournamentLevel, ProtocolError> {
+    match byte {
+        0..=3 => Ok(TournamentLevel::from_byte(byte)),
+        _ => Err(ProtocolError::InvalidValue {
+            field: "tournament level",
+            value: byte.into(),
+        }),
+    }
+}
+
+fn driverstation_status_from_byte(byte: u8) -> Result<DriverstationStatus, ProtocolError> {
+    match byte {
+        0..=2 => Ok(DriverstationStatus::from_byte(byte)),
+        _ => Err(ProtocolError::InvalidValue {
+            field: "driver station status",
+            value: byte.into(),
+        }),
+    }
+}
+
+fn version_type_from_byte(byte: u8) -> Result<VersionType, ProtocolError> {
+    VersionType::from_byte(byte).map_err(|_| ProtocolError::InvalidValue {
+        field: "version type",
+        value: byte.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control_packet() -> ControlPacket {
+        ControlPacket {
+            sequence_num: 0xbeef,
+            comm_version: 0,
+            emergency_stop: true,
+            enabled: true,
+            mode: Mode::Autonomous,
+            request: 0,
+            alliance_station: AllianceStation::Blue2,
+            tournament_level: TournamentLevel::Qualification,
+            match_number: 42,
+            play_number: 3,
+            time: PacketTime {
+                microsecond: 123_456,
+                second: 7,
+                minute: 8,
+                hour: 9,
+                day: 18,
+                month: 10,
+                year: 2026,
+            },
+            time_remaining: 135,
+        }
+    }
+
+    fn status_packet() -> StatusPacket {
+        StatusPacket {
+            sequence_num: 65535,
+            comm_version: 0,
+            emergency_stopped: false,
+            robot_communications_active: true,
+            can_ping_radio: true,
+            can_ping_rio: true,
+            enabled: true,
+            mode: Mode::TeleOp,
+            team_number: 5276,
+            battery_voltage: 12.5,
+            tags: vec![
+                DriverStationTag::FieldRadioMetrics(RadioMetrics {
+                    signal_strength: 80,
+                    bandwidth_utilization: 288,
+                }),
+                DriverStationTag::CommsMetrics(CommsMetrics {
+                    lost_packets: 3,
+                    sent_packets: 200,
+                    average_trip_time: 7,
+                }),
+                DriverStationTag::LaptopMetrics(LaptopMetrics {
+                    battery_percent: 95,
+                    cpu_percent: 12,
+                }),
+                DriverStationTag::RobotRadioMetrics(RadioMetrics {
+                    signal_strength: 60,
+                    bandwidth_utilization: 9,
+                }),
+                DriverStationTag::Unknown {
+                    id: 0x09,
+                    data: vec![1, 2, 3],
+                },
+            ],
+            tag_error: None,
+        }
+    }
+
+    #[test]
+    fn control_packet_round_trip() {
+        let packet = control_packet();
+        let encoded = packet.encode().unwrap();
+        assert_eq!(encoded.len(), 22);
+        assert_eq!(ControlPacket::decode(&encoded).unwrap(), packet);
+    }
+
+    #[test]
+    fn control_packet_wire_format() {
+        const YEAR_OFFSET: usize = 19;
+        const TIME_REMAINING_OFFSET: usize = 20;
+
+        let encoded = control_packet().encode().unwrap();
+        assert_eq!(
+            &encoded[..9],
+            &[0xbe, 0xef, 0x00, 0x86, 0x00, 0x04, 0x02, 0x00, 42]
+        );
+        assert_eq!(encoded[YEAR_OFFSET], 126);
+        assert_eq!(encoded[TIME_REMAINING_OFFSET..], [0x00, 135]);
+    }
+
+    #[test]
+    fn control_packet_rejects_unencodable_year() {
+        let mut packet = control_packet();
+        packet.time.year = 1899;
+        assert_eq!(
+            packet.encode(),
+            Err(ProtocolError::InvalidValue {
+                field: "year",
+                value: 1899
+            })
+        );
+    }
+
+    #[test]
+    fn status_packet_round_trip() {
+        let packet = status_packet();
+        let encoded = packet.encode().unwrap();
+        assert_eq!(StatusPacket::decode(&encoded).unwrap(), packet);
+    }
+
+    #[test]
+    fn status_packet_wire_format() {
+        let packet = StatusPacket {
+            tags: Vec::new(),
+            ..status_packet()
+        };
+        assert_eq!(
+            packet.encode().unwrap(),
+            [0xff, 0xff, 0x00, 0x3c, 0x14, 0x9c, 0x0c, 0x80]
+        );
+    }
+
+    #[test]
+    fn status_packet_rejects_truncated_header() {
+        let encoded = status_packet().encode().unwrap();
+        assert_eq!(
+            StatusPacket::decode(&encoded[..5]),
+            Err(ProtocolError::Truncated {
+                packet: "Status",
+                needed: 2,
+                remaining: 1
+            })
+        );
+        assert!(StatusPacket::decode(&[]).is_err());
+    }
+
+    #[test]
+    fn status_packet_reads_unused_mode_as_teleop() {
+        let mut encoded = status_packet().encode().unwrap();
+        encoded[3] |= 0x03;
+        assert_eq!(
+            StatusPacket::decode(&encoded).unwrap(),
+            StatusPacket {
+                mode: Mode::TeleOp,
+                ..status_packet()
+            }
+        );
+    }
+
+    #[test]
+    fn status_packet_keeps_tags_before_an_overrun() {
+        let mut encoded = StatusPacket {
+            tags: status_packet().tags[..1].to_vec(),
+            ..status_packet()
+        }
+        .encode()
+        .unwrap();
+        encoded.extend_from_slice(&[9, 0x01]);
+
+        let decoded = StatusPacket::decode(&encoded).unwrap();
+        assert_eq!(decoded.tags, status_packet().tags[..1]);
+        assert_eq!(
+            decoded.tag_error,
+            Some(ProtocolError::TagOverrun {
+                size: 9,
+                remaining: 1
+            })
+        );
+    }
+
+    #[test]
+    fn known_tag_with_unexpected_length_is_kept_raw() {
+        let mut tags = Vec::new();
+        DriverStationTag::decode_all(&[3, 0x01, 0xaa, 0xbb, 0, 1, 0x02], &mut tags).unwrap();
+        assert_eq!(
+            tags,
+            [
+                DriverStationTag::Unknown {
+                    id: 0x01,
+                    data: vec![0xaa, 0xbb]
+                },
+                DriverStationTag::Unknown {
+                    id: 0x02,
+                    data: Vec::new()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn fms_tcp_packets_round_trip() {
+        for packet in [
+            FmsTcpPacket::EventCode("2026cmp".to_string()),
+            FmsTcpPacket::StationInfo {
+                alliance_station: AllianceStation::Red3,
+                status: DriverstationStatus::Bad,
+            },
+            FmsTcpPacket::GameData("RBL".to_string()),
+            FmsTcpPacket::GameData(String::new()),
+        ] {
+            let framed = packet.encode_framed().unwrap();
+            let length = frame_length([framed[0], framed[1]]);
+            assert_eq!(length, framed.len() - 2);
+            assert_eq!(FmsTcpPacket::decode(&framed[2..]).unwrap(), packet);
+        }
+    }
+
+    #[test]
+    fn fms_tcp_packet_wire_format() {
+        assert_eq!(
+            FmsTcpPacket::EventCode("nvmre".to_string())
+                .encode_framed()
+                .unwrap(),
+            [0x00, 0x07, 0x14, 0x05, b'n', b'v', b'm', b'r', b'e']
+        );
+        assert_eq!(
+            FmsTcpPacket::StationInfo {
+                alliance_station: AllianceStation::Blue1,
+                status: DriverstationStatus::Waiting,
+            }
+            .encode_framed()
+            .unwrap(),
+            [0x00, 0x03, 0x19, 0x03, 0x02]
+        );
+    }
+
+    #[test]
+    fn fms_tcp_packet_rejects_long_strings() {
+        assert_eq!(
+            FmsTcpPacket::GameData("x".repeat(256)).encode(),
+            Err(ProtocolError::StringTooLong {
+                field: "Game data",
+                length: 256
+            })
+        );
+    }
+
+    #[test]
+    fn fms_tcp_packet_rejects_malformed_input() {
+        assert_eq!(FmsTcpPacket::decode(&[]), Err(ProtocolError::EmptyFrame));
+        assert_eq!(
+            FmsTcpPacket::decode(&[0x14, 0x05, b'a']),
+            Err(ProtocolError::Truncated {
+                packet: "FMS TCP",
+                needed: 5,
+                remaining: 1
+            })
+        );
+        assert_eq!(
+            FmsTcpPacket::decode(&[0x19, 0x06, 0x00]),
+            Err(ProtocolError::InvalidValue {
+                field: "alliance station",
+                value: 6
+            })
+        );
+        assert_eq!(
+            FmsTcpPacket::decode(&[0x1c, 0x01, 0xff]),
+            Err(ProtocolError::InvalidUtf8 { packet: "FMS TCP" })
+        );
+    }
+
+    #[test]
+    fn ds_tcp_packets_round_trip() {
+        for packet in [
+            DsTcpPacket::Version(VersionData {
+                version_type: VersionType::RoboRIO,
+                status: "Good".to_string(),
+                version: "2026.1.1".to_string(),
+            }),
+            DsTcpPacket::LogData(LogDataPacket {
+                trip_time: 12,
+                lost_packets: 4,
+                voltage: 12.25,
+                brownout: true,
+                watchdog: false,
+                ds_teleop: true,
+                ds_auto: false,
+                ds_disable: false,
+                robot_teleop: true,
+                robot_auto: false,
+                robot_disable: true,
+                can_utilization: 40,
+                signal: 90,
+                bandwidth: 3.5,
+            }),
+            DsTcpPacket::LogMessage(LogMessagePacket {
+                message_count: 1,
+                timestamp: 1_792_300_000,
+                timestamp_fraction: 42,
+                sequence_num: 7,
+                message: "Robot code started".to_string(),
+            }),
+            DsTcpPacket::TeamNumber(5276),
+            DsTcpPacket::KeepAlive,
+            DsTcpPacket::Unknown {
+                id: 0x99,
+                data: vec![1, 2],
+            },
+        ] {
+            let framed = packet.encode_framed().unwrap();
+            assert_eq!(frame_length([framed[0], framed[1]]), framed.len() - 2);
+            assert_eq!(DsTcpPacket::decode(&framed[2..]).unwrap(), packet);
+        }
+    }
+
+    #[test]
+    fn ds_tcp_packet_wire_format() {
+        assert_eq!(
+            DsTcpPacket::TeamNumber(254).encode_framed().unwrap(),
+            [0x00, 0x03, 0x18, 0x00, 0xfe]
+        );
+        assert_eq!(
+            DsTcpPacket::KeepAlive.encode_framed().unwrap(),
+            [0x00, 0x01, 0x1d]
+        );
+    }
+
+    #[test]
+    fn ds_tcp_packet_rejects_malformed_input() {
+        assert_eq!(DsTcpPacket::decode(&[]), Err(ProtocolError::EmptyFrame));
+        assert_eq!(
+            DsTcpPacket::decode(&[0x18, 0x01]),
+            Err(ProtocolError::Truncated {
+                packet: "DS TCP",
+                needed: 2,
+                remaining: 1
+            })
+        );
+        assert!(matches!(
+            DsTcpPacket::decode(&[0x16, 0x00, 0x00, 0x0c]),
+            Err(ProtocolError::Truncated { .. })
+        ));
+        // A timestamp from before the UNIX epoch
+        let mut log_message = vec![0x17, 0, 0, 0, 1];
+        log_message.extend_from_slice(&5u64.to_be_bytes());
+        log_message.extend_from_slice(&[0; 12]);
+        assert_eq!(
+            DsTcpPacket::decode(&log_message),
+            Err(ProtocolError::InvalidValue {
+                field: "log message timestamp",
+                value: 5
+            })
+        );
+        assert_eq!(
+            DsTcpPacket::decode(&[0x02, 0xff, 0xfe]),
+            Err(ProtocolError::InvalidUtf8 { packet: "Version" })
+        );
+    }
+
+    #[test]
+    fn log_message_with_invalid_utf8_is_kept() {
+        let mut log_message = vec![0x17, 0, 0, 0, 1];
+        log_message.extend_from_slice(&LABVIEW_EPOCH_OFFSET.to_be_bytes());
+        log_message.extend_from_slice(&[0; 12]);
+        log_message.extend_from_slice(b"bad \xff byte");
+        let DsTcpPacket::LogMessage(packet) = DsTcpPacket::decode(&log_message).unwrap() else {
+            panic!("expected a log message");
+        };
+        assert_eq!(packet.message, "bad \u{fffd} byte");
+    }
+
+    #[test]
+    fn version_without_status_decodes_empty() {
+        assert_eq!(
+            DsTcpPacket::decode(b"\x00garbage").unwrap(),
+            DsTcpPacket::Version(VersionData {
+                version_type: VersionType::WPILib,
+                status: String::new(),
+                version: String::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn frame_rejects_oversized_packets() {
+        assert_eq!(
+            frame(&vec![0; 70_000]),
+            Err(ProtocolError::FrameTooLong(70_000))
+        );
+    }
+}
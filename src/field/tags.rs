@@ -0,0 +1,29 @@
+use std::collections::BTreeMap;
+
+use super::protocol::{CommsMetrics, DriverStationTag, LaptopMetrics, RadioMetrics};
+
+/// The latest value of every tag a driver station has sent. Not every packet carries every
+/// tag, so each one replaces only its own entry.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DriverStationDiagnostics {
+    pub field_radio: Option<RadioMetrics>,
+    pub comms: Option<CommsMetrics>,
+    pub laptop: Option<LaptopMetrics>,
+    pub robot_radio: Option<RadioMetrics>,
+    /// Raw data of undecoded tags by id
+    pub unknown_tags: BTreeMap<u8, Vec<u8>>,
+}
+
+impl DriverStationDiagnostics {
+    pub(super) fn apply(&mut self, tag: DriverStationTag) {
+        match tag {
+            DriverStationTag::FieldRadioMetrics(metrics) => self.field_radio = Some(metrics),
+            DriverStationTag::CommsMetrics(metrics) => self.comms = Some(metrics),
+            DriverStationTag::LaptopMetrics(metrics) => self.laptop = Some(metrics),
+            DriverStationTag::RobotRadioMetrics(metrics) => self.robot_radio = Some(metrics),
+            DriverStationTag::Unknown { id, data } => {
+                self.unknown_tags.insert(id, data);
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::bail;
+use serde::Deserialize;
+
+use super::enums::TournamentLevel;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The length of every period of a match. Loaded from the `[timing.profiles.<name>]` tables
+/// of the config file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MatchTimingProfile {
+    pub auto_secs: u64,
+    pub transition_secs: u64,
+    pub teleop_secs: u64,
+    /// How many seconds before the end of teleop the endgame begins
+    pub endgame_warning_secs: u64,
+    /// How long the field stays unsafe after the match ends
+    pub post_match_hold_secs: u64,
+}
+
+impl MatchTimingProfile {
+    pub fn auto(&self) -> Duration {
+        Duration::from_secs(self.auto_secs)
+    }
+
+    pub fn transition(&self) -> Duration {
+        Duration::from_secs(self.transition_secs)
+    }
+
+    pub fn teleop(&self) -> Duration {
+        Duration::from_secs(self.teleop_secs)
+    }
+
+    pub fn endgame_warning(&self) -> Duration {
+        Duration::from_secs(self.endgame_warning_secs)
+    }
+
+    pub fn post_match_hold(&self) -> Duration {
+        Duration::from_secs(self.post_match_hold_secs)
+    }
+}
+
+impl Default for MatchTimingProfile {
+    fn default() -> Self {
+        Self {
+            auto_secs: 15,
+            transition_secs: 3,
+            teleop_secs: 135,
+            endgame_warning_secs: 20,
+            post_match_hold_secs: 3,
+        }
+    }
+}
+
+/// The name of the timing profile used for each tournament level.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TournamentLevelProfiles {
+    pub test: String,
+    pub practice: String,
+    pub qualification: String,
+    pub playoff: String,
+}
+
+impl Default for TournamentLevelProfiles {
+    fn default() -> Self {
+        Self {
+            test: DEFAULT_PROFILE.to_string(),
+            practice: DEFAULT_PROFILE.to_string(),
+            qualification: DEFAULT_PROFILE.to_string(),
+            playoff: DEFAULT_PROFILE.to_string(),
+        }
+    }
+}
+
+/// All known timing profiles and which one is selected for each tournament level.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MatchTimings {
+    profiles: HashMap<String, MatchTimingProfile>,
+    levels: TournamentLevelProfiles,
+}
+
+impl MatchTimings {
+    pub fn profiles(&self) -> &HashMap<String, MatchTimingProfile> {
+        &self.profiles
+    }
+
+    pub fn profile_name(&self, tournament_level: TournamentLevel) -> &str {
+        match tournament_level {
+            TournamentLevel::Test => &self.levels.test,
+            TournamentLevel::Practice => &self.levels.practice,
+            TournamentLevel::Qualification => &self.levels.qualification,
+            TournamentLevel::Playoff => &self.levels.playoff,
+        }
+    }
+
+    pub fn profile(&self, tournament_level: TournamentLevel) -> MatchTimingProfile {
+        self.profiles
+            .get(self.profile_name(tournament_level))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_profile_name(
+        &mut self,
+        tournament_level: TournamentLevel,
+        name: &str,
+    ) -> anyhow::Result<()> {
+        if !self.profiles.contains_key(name) {
+            bail!("No timing profile named {} exists", name);
+        }
+        let name = name.to_string();
+        match tournament_level {
+            TournamentLevel::Test => self.levels.test = name,
+            TournamentLevel::Practice => self.levels.practice = name,
+            TournamentLevel::Qualification => self.levels.qualification = name,
+            TournamentLevel::Playoff => self.levels.playoff = name,
+        }
+        Ok(())
+    }
+
+    /// Makes sure the built-in default profile exists and that every tournament level points
+    /// at a known profile.
+    pub fn validate(&mut self) -> anyhow::Result<()> {
+        self.profiles
+            .entry(DEFAULT_PROFILE.to_string())
+            .or_default();
+
+        for tournament_level in TournamentLevel::ALL {
+            let name = self.profile_name(tournament_level);
+            if !self.profiles.contains_key(name) {
+                bail!(
+                    "Tournament level {} uses timing profile {} which does not exist",
+                    tournament_level,
+                    name
+                );
+            }
+        }
+
+        for (name, profile) in self.profiles.iter() {
+            if profile.endgame_warning_secs > profile.teleop_secs {
+                bail!("Timing profile {name} has an endgame longer than teleop");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for MatchTimings {
+    fn default() -> Self {
+        Self {
+            profiles: HashMap::from([(DEFAULT_PROFILE.to_string(), MatchTimingProfile::default())]),
+            levels: TournamentLevelProfiles::default(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use async_graphql::{Context, Guard, Result};
+
+use crate::auth::{Scope, Session};
+
+/// Rejects the request unless it comes from a signed in user or an API key
+pub struct SignedInGuard;
+
+impl Guard for SignedInGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        match ctx.data_opt::<Session>() {
+            Some(_) => Ok(()),
+            None => Err("Not signed in".into()),
+        }
+    }
+}
+
+/// Rejects the request unless the signed in user or API key has been granted the scope
+pub struct ScopeGuard(pub Scope);
+
+impl Guard for ScopeGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        match ctx.data_opt::<Session>() {
+            Some(session) if session.has_scope(self.0) => Ok(()),
+            Some(_) => Err(format!("Missing scope {}", self.0).into()),
+            None => Err("Not signed in".into()),
+        }
+    }
+}
+
+/// Rejects overriding a safety check, when one is asked for, unless the signed in user or API
+/// key has been granted `Scope::FieldOverride`
+pub struct OverrideGuard(pub bool);
+
+impl Guard for OverrideGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        if !self.0 {
+            return Ok(());
+        }
+        ScopeGuard(Scope::FieldOverride).check(ctx).await
+    }
+}
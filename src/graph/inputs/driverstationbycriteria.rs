@@ -1,5 +1,6 @@
 use async_graphql::*;
 
+use crate::field::driverstation::{DriverStation, DriverStations};
 use crate::graph::types::*;
 
 #[derive(OneofObject)]
@@ -7,4 +8,17 @@ use crate::graph::types::*;
 pub enum GQLDriverStationByCriteriaInput {
     TeamNumber(u16),
     AllianceStation(GQLAllianceStation)
-}
\ No newline at end of file
+}
+
+impl GQLDriverStationByCriteriaInput {
+    pub fn find(&self, driverstations: &DriverStations) -> Option<DriverStation> {
+        match self {
+            GQLDriverStationByCriteriaInput::TeamNumber(team_number) => {
+                driverstations.get_driverstation_by_team_number(*team_number)
+            }
+            GQLDriverStationByCriteriaInput::AllianceStation(alliance_station) => {
+                driverstations.get_driverstation_by_position((*alliance_station).into())
+            }
+        }
+    }
+}
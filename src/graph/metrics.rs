@@ -0,0 +1,88 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use async_graphql::{
+    Response, ServerResult, Value,
+    extensions::{
+        Extension, ExtensionContext, ExtensionFactory, NextExecute, NextResolve, ResolveInfo,
+    },
+};
+
+use crate::field::Field;
+
+/// Times every GraphQL query and mutation for the Prometheus `/metrics` endpoint, labelled by the
+/// root field the request resolved.
+///
+/// Root field names come from the schema rather than the client, so the number of label values
+/// stays bounded no matter what operation names callers send.
+pub struct RequestMetrics;
+
+impl ExtensionFactory for RequestMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(RequestMetricsExtension::default())
+    }
+}
+
+#[derive(Default)]
+struct RequestMetricsExtension {
+    root_field: Mutex<RootField>,
+}
+
+#[derive(Default)]
+enum RootField {
+    #[default]
+    None,
+    Single(String),
+    Multiple,
+}
+
+impl RootField {
+    fn label(&self) -> &str {
+        match self {
+            RootField::None => "other",
+            RootField::Single(name) => name,
+            RootField::Multiple => "multiple",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for RequestMetricsExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let started_at = Instant::now();
+        let response = next.run(ctx, operation_name).await;
+        if let Some(field) = ctx.data_opt::<Field>() {
+            let root_field = self.root_field.lock().unwrap();
+            field.metrics().record_graphql_request(
+                root_field.label(),
+                response.is_ok(),
+                started_at.elapsed(),
+            );
+        }
+        response
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if info.path_node.parent.is_none() {
+            let mut root_field = self.root_field.lock().unwrap();
+            *root_field = match &*root_field {
+                RootField::None => RootField::Single(info.name.to_string()),
+                RootField::Single(name) if name == info.name => RootField::Single(name.clone()),
+                _ => RootField::Multiple,
+            };
+        }
+        next.run(ctx, info).await
+    }
+}
@@ -1,5 +1,45 @@
+pub mod guards;
 pub mod inputs;
+pub mod metrics;
 pub mod mutation;
 pub mod query;
 pub mod schema;
+pub mod subscription;
 pub mod types;
+
+use anyhow::Context as _;
+use async_graphql::Context;
+use serde_json::Value;
+
+use crate::{auth::Session, field::Field};
+
+/// The session of the user or API key making the request
+pub fn session<'a>(ctx: &Context<'a>) -> anyhow::Result<&'a Session> {
+    ctx.data_opt::<Session>().context("Not signed in")
+}
+
+/// The name recorded against actions taken through the API
+pub fn actor_name(ctx: &Context<'_>) -> String {
+    ctx.data_opt::<Session>()
+        .map(|session| session.actor_name())
+        .unwrap_or_else(|| "anonymous".to_string())
+}
+
+/// Records an operation made through the API in the audit log, under the name of whoever made
+/// the request
+pub fn audit(
+    ctx: &Context<'_>,
+    operation: &str,
+    arguments: Value,
+    previous_value: Option<Value>,
+    new_value: Option<Value>,
+) {
+    let field = ctx.data::<Field>().unwrap();
+    field.audit_log().record(
+        &actor_name(ctx),
+        operation,
+        arguments,
+        previous_value,
+        new_value,
+    );
+}
@@ -1,55 +1,576 @@
 #![allow(clippy::unused_async)]
 
-use anyhow::bail;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
 use async_graphql::*;
+use serde_json::json;
 
+use crate::auth::{Auth, Scope};
 use crate::field::Field;
+use crate::field::driverstation::DriverStation;
+use crate::field::gamedata::GameData;
+use crate::graph::guards::{OverrideGuard, ScopeGuard, SignedInGuard};
 use crate::graph::inputs::*;
 use crate::graph::types::*;
+use crate::graph::{actor_name, audit, session};
 
 pub struct Mutation;
 
 #[allow(unreachable_code)]
 #[Object]
 impl Mutation {
-    //TODO Auth
+    async fn sign_in(
+        &self,
+        ctx: &Context<'_>,
+        username: String,
+        password: String,
+    ) -> anyhow::Result<GQLToken> {
+        let auth = ctx.data::<Auth>().unwrap().clone();
+        let field = ctx.data::<Field>().unwrap();
+        // Password hashing is slow on purpose, keep it off the async runtime
+        let token = {
+            let username = username.clone();
+            tokio::task::spawn_blocking(move || auth.sign_in(&username, &password)).await??
+        };
+        field.audit_log().record(
+            &username,
+            "signIn",
+            json!({ "username": username }),
+            None,
+            None,
+        );
+        Ok(GQLToken { obj_token: token })
+    }
+
+    #[graphql(guard = "SignedInGuard")]
+    async fn sign_out(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+        let auth = ctx.data::<Auth>().unwrap();
+        auth.sign_out(session(ctx)?)?;
+        audit(ctx, "signOut", json!({}), None, None);
+        Ok(true)
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::UsersManage)")]
+    async fn create_user(
+        &self,
+        ctx: &Context<'_>,
+        username: String,
+        name: String,
+        password: String,
+        scopes: Vec<GQLScope>,
+    ) -> anyhow::Result<GQLUser> {
+        let auth = ctx.data::<Auth>().unwrap().clone();
+        let scopes: Vec<Scope> = scopes.into_iter().map(Scope::from).collect();
+        let user = tokio::task::spawn_blocking(move || {
+            auth.create_user(&username, &name, &password, &scopes)
+        })
+        .await??;
+        audit(
+            ctx,
+            "createUser",
+            json!({ "username": user.username, "name": user.name }),
+            None,
+            Some(json!(scope_names(&user.scopes))),
+        );
+        Ok(GQLUser { obj_user: user })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::UsersManage)")]
+    async fn set_user_scopes(
+        &self,
+        ctx: &Context<'_>,
+        username: String,
+        scopes: Vec<GQLScope>,
+    ) -> anyhow::Result<GQLUser> {
+        let auth = ctx.data::<Auth>().unwrap();
+        let scopes: Vec<Scope> = scopes.into_iter().map(Scope::from).collect();
+        let previous_scopes = auth
+            .users()?
+            .into_iter()
+            .find(|user| user.username == username)
+            .map(|user| scope_names(&user.scopes));
+        let user = auth.set_user_scopes(&username, &scopes)?;
+        audit(
+            ctx,
+            "setUserScopes",
+            json!({ "username": username }),
+            previous_scopes.map(|scopes| json!(scopes)),
+            Some(json!(scope_names(&user.scopes))),
+        );
+        Ok(GQLUser { obj_user: user })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::UsersManage)")]
+    async fn create_api_key(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        scopes: Vec<GQLScope>,
+    ) -> anyhow::Result<GQLNewApiKey> {
+        let auth = ctx.data::<Auth>().unwrap();
+        let scopes: Vec<Scope> = scopes.into_iter().map(Scope::from).collect();
+        let new_api_key = auth.create_api_key(&name, &scopes, &actor_name(ctx))?;
+        audit(
+            ctx,
+            "createApiKey",
+            json!({ "id": new_api_key.api_key.id, "name": name }),
+            None,
+            Some(json!(scope_names(&scopes))),
+        );
+        Ok(GQLNewApiKey {
+            obj_new_api_key: new_api_key,
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::UsersManage)")]
+    async fn revoke_api_key(&self, ctx: &Context<'_>, id: ID) -> anyhow::Result<GQLApiKey> {
+        let auth = ctx.data::<Auth>().unwrap();
+        let id: i64 = id
+            .parse()
+            .map_err(|_| anyhow!("Invalid API key id {}", *id))?;
+        let api_key = auth.revoke_api_key(id, &actor_name(ctx))?;
+        audit(
+            ctx,
+            "revokeApiKey",
+            json!({ "id": id, "name": api_key.name }),
+            None,
+            Some(json!({ "revokedAt": api_key.revoked_at })),
+        );
+        Ok(GQLApiKey {
+            obj_api_key: api_key,
+        })
+    }
 
-    #[graphql(name = "clearFMSAlarm")]
+    #[graphql(name = "clearFMSAlarm", guard = "ScopeGuard(Scope::AlarmsClear)")]
     async fn clear_fms_alarm(&self, ctx: &Context<'_>, code: String) -> anyhow::Result<bool> {
         let field = ctx.data::<Field>().unwrap();
-        field.alarm_handler().clear_alarm(&code)
+        let cleared = field.alarm_handler().clear_alarm(&code)?;
+        audit(
+            ctx,
+            "clearFMSAlarm",
+            json!({ "code": code }),
+            None,
+            Some(json!({ "cleared": cleared })),
+        );
+        Ok(cleared)
     }
 
-    #[graphql(name = "clearAllFMSAlarms")]
+    #[graphql(name = "clearAllFMSAlarms", guard = "ScopeGuard(Scope::AlarmsClear)")]
     async fn clear_all_fms_alarms(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
         let field = ctx.data::<Field>().unwrap();
-        field.alarm_handler().clear_all_alarms()
+        let previous_codes = active_alarm_codes(field);
+        let cleared = field.alarm_handler().clear_all_alarms()?;
+        audit(
+            ctx,
+            "clearAllFMSAlarms",
+            json!({}),
+            Some(json!(previous_codes)),
+            Some(json!(active_alarm_codes(field))),
+        );
+        Ok(cleared)
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn prestart_match(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_state = field.match_state();
+        field.prestart_match()?;
+        audit(
+            ctx,
+            "prestartMatch",
+            json!({}),
+            Some(json!(previous_state.to_string())),
+            Some(json!(field.match_state().to_string())),
+        );
+        Ok(true)
+    }
+
+    #[graphql(
+        guard = "ScopeGuard(Scope::FieldControl).and(OverrideGuard(override_prestart_check))"
+    )]
+    async fn start_match(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = false)] override_prestart_check: bool,
+    ) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_state = field.match_state();
+        field.start_match(override_prestart_check)?;
+        audit(
+            ctx,
+            "startMatch",
+            json!({ "overridePrestartCheck": override_prestart_check }),
+            Some(json!(previous_state.to_string())),
+            Some(json!(field.match_state().to_string())),
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn stop_match(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_state = field.match_state();
+        field.stop_match()?;
+        audit(
+            ctx,
+            "stopMatch",
+            json!({}),
+            Some(json!(previous_state.to_string())),
+            Some(json!(field.match_state().to_string())),
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn pause_match(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_paused = field.match_paused();
+        field.pause_match()?;
+        audit(
+            ctx,
+            "pauseMatch",
+            json!({ "matchState": field.match_state().to_string() }),
+            Some(json!(previous_paused)),
+            Some(json!(field.match_paused())),
+        );
+        Ok(true)
+    }
+
+    #[graphql(name = "unPauseMatch", guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn unpause_match(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_paused = field.match_paused();
+        field.unpause_match()?;
+        audit(
+            ctx,
+            "unPauseMatch",
+            json!({ "matchState": field.match_state().to_string() }),
+            Some(json!(previous_paused)),
+            Some(json!(field.match_paused())),
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn commit_match(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_state = field.match_state();
+        field.commit_match()?;
+        audit(
+            ctx,
+            "commitMatch",
+            json!({}),
+            Some(json!(previous_state.to_string())),
+            Some(json!(field.match_state().to_string())),
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn discard_match(&self, ctx: &Context<'_>) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_state = field.match_state();
+        field.discard_match()?;
+        audit(
+            ctx,
+            "discardMatch",
+            json!({}),
+            Some(json!(previous_state.to_string())),
+            Some(json!(field.match_state().to_string())),
+        );
+        Ok(true)
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_event_name(
+        &self,
+        ctx: &Context<'_>,
+        event_name: String,
+    ) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_event_name = field.event_name();
+        field.set_event_name(event_name.clone())?;
+        audit(
+            ctx,
+            "setEventName",
+            json!({ "eventName": event_name }),
+            Some(json!(previous_event_name)),
+            Some(json!(field.event_name())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_tournament_level(
+        &self,
+        ctx: &Context<'_>,
+        tournament_level: GQLTournamentLevel,
+    ) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        if field.match_state().is_running() {
+            bail!("Cannot change the tournament level while a match is running");
+        }
+        let previous_tournament_level = field.tournament_level();
+        field.set_tournament_level(tournament_level.into());
+        audit(
+            ctx,
+            "setTournamentLevel",
+            json!({ "tournamentLevel": field.tournament_level().to_string() }),
+            Some(json!(previous_tournament_level.to_string())),
+            Some(json!(field.tournament_level().to_string())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_match_number(
+        &self,
+        ctx: &Context<'_>,
+        match_number: u16,
+    ) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        if field.match_state().is_running() {
+            bail!("Cannot change the match number while a match is running");
+        }
+        let previous_match_number = field.match_number();
+        field.set_match_number(match_number);
+        audit(
+            ctx,
+            "setMatchNumber",
+            json!({ "matchNumber": match_number }),
+            Some(json!(previous_match_number)),
+            Some(json!(field.match_number())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_play_number(
+        &self,
+        ctx: &Context<'_>,
+        play_number: u8,
+    ) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        if field.match_state().is_running() {
+            bail!("Cannot change the play number while a match is running");
+        }
+        let previous_play_number = field.play_number();
+        field.set_play_number(play_number);
+        audit(
+            ctx,
+            "setPlayNumber",
+            json!({ "playNumber": play_number }),
+            Some(json!(previous_play_number)),
+            Some(json!(field.play_number())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_time_remaining(
+        &self,
+        ctx: &Context<'_>,
+        seconds: f64,
+    ) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        let time_remaining = Duration::try_from_secs_f64(seconds)
+            .map_err(|_| anyhow!("Time remaining must be a positive number of seconds"))?;
+        if time_remaining.as_secs() > u16::MAX as u64 {
+            bail!("Time remaining cannot be longer than {} seconds", u16::MAX);
+        }
+        if field.match_state().is_running() {
+            bail!("Cannot change the time remaining while a match is running");
+        }
+        let previous_time_remaining = field.timer().current_time_remaining();
+        field.set_time_remaining(time_remaining);
+        audit(
+            ctx,
+            "setTimeRemaining",
+            json!({ "seconds": seconds }),
+            Some(json!(previous_time_remaining.as_secs_f64())),
+            Some(json!(field.timer().current_time_remaining().as_secs_f64())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn start_timer(&self, ctx: &Context<'_>) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        if field.match_state().is_running() {
+            bail!("Cannot start the timer while a match is running");
+        }
+        let previous_running = field.timer().is_running();
+        field.start_timer();
+        audit(
+            ctx,
+            "startTimer",
+            json!({}),
+            Some(json!(previous_running)),
+            Some(json!(field.timer().is_running())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn stop_timer(&self, ctx: &Context<'_>) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        if field.match_state().is_running() {
+            bail!("Cannot stop the timer while a match is running, pause the match instead");
+        }
+        let previous_running = field.timer().is_running();
+        field.stop_timer();
+        audit(
+            ctx,
+            "stopTimer",
+            json!({}),
+            Some(json!(previous_running)),
+            Some(json!(field.timer().is_running())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(name = "setDSMode", guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_ds_mode(&self, ctx: &Context<'_>, mode: GQLMode) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        if field.match_state().is_running() {
+            bail!("Cannot change the DS mode while a match is running");
+        }
+        let previous_ds_mode = field.ds_mode();
+        field.set_ds_mode(mode.into());
+        audit(
+            ctx,
+            "setDSMode",
+            json!({ "mode": field.ds_mode().to_string() }),
+            Some(json!(previous_ds_mode.to_string())),
+            Some(json!(field.ds_mode().to_string())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_is_safe(&self, ctx: &Context<'_>, is_safe: bool) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        let previous_is_safe = field.is_safe();
+        field.set_is_safe(is_safe)?;
+        audit(
+            ctx,
+            "setIsSafe",
+            json!({ "isSafe": is_safe }),
+            Some(json!(previous_is_safe)),
+            Some(json!(field.is_safe())),
+        );
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_timing_profile(
+        &self,
+        ctx: &Context<'_>,
+        tournament_level: GQLTournamentLevel,
+        name: String,
+    ) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        let tournament_level = tournament_level.into();
+        let previous_name = field.timings().profile_name(tournament_level).to_string();
+        field.set_timing_profile(tournament_level, &name)?;
+        audit(
+            ctx,
+            "setTimingProfile",
+            json!({ "tournamentLevel": tournament_level.to_string(), "name": name }),
+            Some(json!(previous_name)),
+            Some(json!(field.timings().profile_name(tournament_level))),
+        );
+        Ok(true)
+    }
+
+    /// Sets the game data of each of `allianceStations`, so a whole alliance can be given the
+    /// same message at once. A null `message` clears it.
+    #[graphql(guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn set_game_data(
+        &self,
+        ctx: &Context<'_>,
+        alliance_stations: Vec<GQLAllianceStation>,
+        message: Option<String>,
+        #[graphql(default_with = "GQLGameDataRelease::Auto")] release: GQLGameDataRelease,
+    ) -> anyhow::Result<GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap();
+        let game_data = message.map(|message| GameData {
+            message,
+            release: release.into(),
+        });
+        for alliance_station in alliance_stations {
+            let alliance_station = alliance_station.into();
+            let previous_game_data = field.game_data().remove(&alliance_station);
+            field.set_game_data(alliance_station, game_data.clone())?;
+            audit(
+                ctx,
+                "setGameData",
+                json!({ "allianceStation": alliance_station.to_string() }),
+                Some(game_data_json(previous_game_data.as_ref())),
+                Some(game_data_json(game_data.as_ref())),
+            );
+        }
+        Ok(GQLFieldState {
+            obj_field: field.to_owned(),
+        })
     }
 
-    #[graphql(name = "setDS")]
+    #[graphql(name = "setDS", guard = "ScopeGuard(Scope::DsAssign)")]
     async fn set_ds(
         &self,
         ctx: &Context<'_>,
         new_driver_stations: Vec<GQLNewDsInput>,
     ) -> anyhow::Result<Vec<GQLDriverStation>> {
         let field = ctx.data::<Field>().unwrap();
+        if field.match_state().is_running() {
+            bail!("Cannot assign driver stations while a match is running");
+        }
         let driverstations = field.driverstations();
         let mut added_dss = Vec::new();
         for new_ds in new_driver_stations {
+            let mut replaced_dss = Vec::new();
             if let Some(existing_ds) =
                 driverstations.get_driverstation_by_position(new_ds.alliance_station.into())
             {
-                driverstations.delete_driverstation(existing_ds.team_number()).await?;
+                replaced_dss.push(ds_json(&existing_ds));
+                driverstations.delete_driverstation(existing_ds.team_number())?;
             }
 
             if let Some(existing_ds) =
                 driverstations.get_driverstation_by_team_number(new_ds.team_number)
             {
-                driverstations.delete_driverstation(existing_ds.team_number()).await?;
+                replaced_dss.push(ds_json(&existing_ds));
+                driverstations.delete_driverstation(existing_ds.team_number())?;
             }
 
             let added_ds = driverstations
                 .add_driverstation(new_ds.team_number, new_ds.alliance_station.into())?;
+            audit(
+                ctx,
+                "setDS",
+                ds_json(&added_ds),
+                Some(json!(replaced_dss)),
+                Some(ds_json(&added_ds)),
+            );
             added_dss.push(GQLDriverStation {
                 obj_driverstation: added_ds,
             });
@@ -58,29 +579,150 @@ impl Mutation {
         Ok(added_dss)
     }
 
-    #[graphql(name = "removeDS")]
-    async fn remove_ds(
+    #[graphql(name = "enableDS", guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn enable_ds(
         &self,
         ctx: &Context<'_>,
         criteria: GQLDriverStationByCriteriaInput,
-    ) -> anyhow::Result<bool> {
+    ) -> anyhow::Result<GQLDriverStation> {
         let field = ctx.data::<Field>().unwrap();
-        let driverstations = field.driverstations();
-        let current_ds = match criteria {
-            GQLDriverStationByCriteriaInput::AllianceStation(alliance_station) => {
-                driverstations.get_driverstation_by_position(alliance_station.into())
-            }
-            GQLDriverStationByCriteriaInput::TeamNumber(team_number) => {
-                driverstations.get_driverstation_by_team_number(team_number)
-            }
+        let Some(ds) = criteria.find(&field.driverstations()) else {
+            bail!("DriverStation does not exist")
+        };
+        let previous_value = ds.commanded_enabled();
+        ds.enable(&actor_name(ctx))?;
+        audit(
+            ctx,
+            "enableDS",
+            ds_json(&ds),
+            Some(json!(previous_value)),
+            Some(json!(ds.commanded_enabled())),
+        );
+        Ok(GQLDriverStation {
+            obj_driverstation: ds,
+        })
+    }
+
+    #[graphql(name = "disableDS", guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn disable_ds(
+        &self,
+        ctx: &Context<'_>,
+        criteria: GQLDriverStationByCriteriaInput,
+    ) -> anyhow::Result<GQLDriverStation> {
+        let field = ctx.data::<Field>().unwrap();
+        let Some(ds) = criteria.find(&field.driverstations()) else {
+            bail!("DriverStation does not exist")
         };
-        if let Some(ds) = current_ds {
-            field
-                .driverstations()
-                .delete_driverstation(ds.team_number()).await?;
-            Ok(true)
-        } else {
+        let previous_value = ds.commanded_enabled();
+        ds.disable(&actor_name(ctx));
+        audit(
+            ctx,
+            "disableDS",
+            ds_json(&ds),
+            Some(json!(previous_value)),
+            Some(json!(ds.commanded_enabled())),
+        );
+        Ok(GQLDriverStation {
+            obj_driverstation: ds,
+        })
+    }
+
+    #[graphql(name = "emergencyStopDS", guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn emergency_stop_ds(
+        &self,
+        ctx: &Context<'_>,
+        criteria: GQLDriverStationByCriteriaInput,
+    ) -> anyhow::Result<GQLDriverStation> {
+        let field = ctx.data::<Field>().unwrap();
+        let Some(ds) = criteria.find(&field.driverstations()) else {
             bail!("DriverStation does not exist")
+        };
+        let previous_value = ds.emergency_stopped();
+        ds.emergency_stop(&actor_name(ctx));
+        audit(
+            ctx,
+            "emergencyStopDS",
+            ds_json(&ds),
+            Some(json!(previous_value)),
+            Some(json!(ds.emergency_stopped())),
+        );
+        Ok(GQLDriverStation {
+            obj_driverstation: ds,
+        })
+    }
+
+    #[graphql(name = "autonomousStopDS", guard = "ScopeGuard(Scope::FieldControl)")]
+    async fn autonomous_stop_ds(
+        &self,
+        ctx: &Context<'_>,
+        criteria: GQLDriverStationByCriteriaInput,
+    ) -> anyhow::Result<GQLDriverStation> {
+        let field = ctx.data::<Field>().unwrap();
+        let Some(ds) = criteria.find(&field.driverstations()) else {
+            bail!("DriverStation does not exist")
+        };
+        let previous_value = ds.autonomous_stopped();
+        ds.autonomous_stop(&actor_name(ctx))?;
+        audit(
+            ctx,
+            "autonomousStopDS",
+            ds_json(&ds),
+            Some(json!(previous_value)),
+            Some(json!(ds.autonomous_stopped())),
+        );
+        Ok(GQLDriverStation {
+            obj_driverstation: ds,
+        })
+    }
+
+    #[graphql(name = "removeDS", guard = "ScopeGuard(Scope::DsAssign)")]
+    async fn remove_ds(
+        &self,
+        ctx: &Context<'_>,
+        criteria: GQLDriverStationByCriteriaInput,
+    ) -> anyhow::Result<bool> {
+        let field = ctx.data::<Field>().unwrap();
+        if field.match_state().is_running() {
+            bail!("Cannot remove driver stations while a match is running");
         }
+        let Some(ds) = criteria.find(&field.driverstations()) else {
+            bail!("DriverStation does not exist")
+        };
+        field
+            .driverstations()
+            .delete_driverstation(ds.team_number())?;
+        audit(ctx, "removeDS", ds_json(&ds), Some(ds_json(&ds)), None);
+        Ok(true)
     }
 }
+
+/// Identifies a driver station in audit log entries
+fn ds_json(ds: &DriverStation) -> serde_json::Value {
+    json!({
+        "teamNumber": ds.team_number(),
+        "allianceStation": ds.alliance_station().to_string(),
+    })
+}
+
+fn game_data_json(game_data: Option<&GameData>) -> serde_json::Value {
+    match game_data {
+        Some(game_data) => json!({
+            "message": game_data.message,
+            "release": format!("{:?}", game_data.release),
+        }),
+        None => serde_json::Value::Null,
+    }
+}
+
+fn active_alarm_codes(field: &Field) -> Vec<String> {
+    field
+        .alarm_handler()
+        .active_alarms()
+        .into_iter()
+        .map(|alarm| alarm.code)
+        .collect()
+}
+
+fn scope_names(scopes: &[Scope]) -> Vec<String> {
+    scopes.iter().map(|scope| scope.to_string()).collect()
+}
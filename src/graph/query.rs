@@ -1,7 +1,14 @@
+#![allow(clippy::unused_async)]
+
+use anyhow::Context as _;
 use async_graphql::*;
 
+use crate::auth::{Auth, Scope};
+use crate::database::AuditFilter;
 use crate::field::Field;
 use crate::graph::inputs::*;
+use crate::graph::guards::{ScopeGuard, SignedInGuard};
+use crate::graph::session;
 use crate::graph::types::*;
 
 pub struct Query;
@@ -9,8 +16,7 @@ pub struct Query;
 #[allow(unreachable_code)]
 #[Object]
 impl Query {
-    //TODO Auth
-
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
     async fn field_state(&self, ctx: &Context<'_>) -> GQLFieldState {
         let field = ctx.data::<Field>().unwrap();
         GQLFieldState {
@@ -18,7 +24,7 @@ impl Query {
         }
     }
 
-    #[graphql(name = "activeFMSAlarms")]
+    #[graphql(name = "activeFMSAlarms", guard = "ScopeGuard(Scope::Readonly)")]
     async fn active_fms_alarms(&self, ctx: &Context<'_>) -> Vec<GQLFMSAlarm> {
         let field = ctx.data::<Field>().unwrap();
         field
@@ -32,7 +38,7 @@ impl Query {
             .collect()
     }
 
-    #[graphql(name = "historicFMSAlarms")]
+    #[graphql(name = "historicFMSAlarms", guard = "ScopeGuard(Scope::Readonly)")]
     async fn historic_fms_alarms(&self, ctx: &Context<'_>) -> Vec<GQLFMSAlarm> {
         let field = ctx.data::<Field>().unwrap();
         field
@@ -46,6 +52,7 @@ impl Query {
             .collect()
     }
 
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
     async fn driver_stations(&self, ctx: &Context<'_>) -> Vec<GQLDriverStation> {
         let field = ctx.data::<Field>().unwrap();
         field
@@ -58,6 +65,7 @@ impl Query {
             .collect()
     }
 
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
     async fn driver_station(
         &self,
         ctx: &Context<'_>,
@@ -80,6 +88,89 @@ impl Query {
         }
     }
 
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
+    async fn prestart_check(&self, ctx: &Context<'_>) -> GQLPreStartCheck {
+        let field = ctx.data::<Field>().unwrap();
+        GQLPreStartCheck {
+            obj_prestartcheck: field.prestart_check(),
+        }
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
+    async fn match_timing_profiles(&self, ctx: &Context<'_>) -> Vec<GQLMatchTimingProfile> {
+        let field = ctx.data::<Field>().unwrap();
+        field
+            .timings()
+            .profiles()
+            .iter()
+            .map(|(name, profile)| GQLMatchTimingProfile {
+                name: name.clone(),
+                obj_matchtimingprofile: profile.clone(),
+            })
+            .collect()
+    }
+
+    #[graphql(guard = "SignedInGuard")]
+    async fn me(&self, ctx: &Context<'_>) -> anyhow::Result<GQLUser> {
+        let user = session(ctx)?
+            .user()
+            .context("API keys are not users")?;
+        Ok(GQLUser {
+            obj_user: user.clone(),
+        })
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::UsersManage)")]
+    async fn users(&self, ctx: &Context<'_>) -> anyhow::Result<Vec<GQLUser>> {
+        let auth = ctx.data::<Auth>().unwrap();
+        Ok(auth
+            .users()?
+            .into_iter()
+            .map(|user| GQLUser { obj_user: user })
+            .collect())
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::UsersManage)")]
+    async fn api_keys(&self, ctx: &Context<'_>) -> anyhow::Result<Vec<GQLApiKey>> {
+        let auth = ctx.data::<Auth>().unwrap();
+        Ok(auth
+            .api_keys()?
+            .into_iter()
+            .map(|api_key| GQLApiKey {
+                obj_api_key: api_key,
+            })
+            .collect())
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
+    async fn audit_log(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<u64>,
+        until: Option<u64>,
+        actor: Option<String>,
+        operation: Option<String>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Vec<GQLAuditEntry>> {
+        let field = ctx.data::<Field>().unwrap();
+        let filter = AuditFilter {
+            since,
+            until,
+            actor,
+            operation,
+            limit,
+        };
+        Ok(field
+            .audit_log()
+            .entries(&filter)?
+            .into_iter()
+            .map(|entry| GQLAuditEntry {
+                obj_auditentry: entry,
+            })
+            .collect())
+    }
+
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
     async fn current_match(&self, ctx: &Context<'_>) -> Option<GQLFieldMatch> {
         let _field = ctx.data::<Field>().unwrap();
         None
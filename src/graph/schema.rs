@@ -1,20 +1,113 @@
-use async_graphql::{EmptySubscription, ObjectType, Schema, SubscriptionType};
-use async_graphql_poem::GraphQL;
+use async_graphql::{
+    Data, Executor, ObjectType, Schema, SubscriptionType, http::ALL_WEBSOCKET_PROTOCOLS,
+};
+use async_graphql_poem::{
+    GraphQLBatchRequest, GraphQLBatchResponse, GraphQLProtocol, GraphQLWebSocket,
+};
+use poem::{FromRequest, IntoResponse, web::websocket::WebSocket};
 
 use crate::{
+    auth::{Auth, Session},
     field::Field,
-    graph::{mutation::Mutation, query::Query},
+    graph::{
+        metrics::RequestMetrics, mutation::Mutation, query::Query, subscription::Subscription,
+    },
 };
 
-
-pub fn create_schema(field: Field) -> Schema<Query, Mutation, EmptySubscription> {
-    Schema::build(Query, Mutation, EmptySubscription)
+pub fn create_schema(field: Field, auth: Auth) -> Schema<Query, Mutation, Subscription> {
+    Schema::build(Query, Mutation, Subscription)
         .data(field)
+        .data(auth)
+        .extension(RequestMetrics)
         .finish()
 }
 
-pub fn create_graphql_endpoint<Q, M, S>(schema: Schema<Q, M, S>) -> GraphQL<Schema<Q, M, S>> {
-    GraphQL::new(schema)
+/// Executes GraphQL requests with the `Session` left in the request by the web server's auth
+/// middleware, if there is one.
+pub struct GraphQLEndpoint<E>(E);
+
+impl<E: Executor> poem::Endpoint for GraphQLEndpoint<E> {
+    type Output = poem::Response;
+
+    async fn call(&self, req: poem::Request) -> poem::Result<poem::Response> {
+        let session = req.extensions().get::<Session>().cloned();
+        let (req, mut body) = req.split();
+        let mut batch_request = GraphQLBatchRequest::from_request(&req, &mut body).await?.0;
+        if let Some(session) = session {
+            batch_request = batch_request.data(session);
+        }
+        Ok(GraphQLBatchResponse(self.0.execute_batch(batch_request).await).into_response())
+    }
+}
+
+pub fn create_graphql_endpoint<Q, M, S>(
+    schema: Schema<Q, M, S>,
+) -> GraphQLEndpoint<Schema<Q, M, S>> {
+    GraphQLEndpoint(schema)
+}
+
+/// Serves GraphQL subscriptions over a websocket. Browsers cannot set headers on a websocket,
+/// so the bearer token may also be sent as `Authorization` in the `connection_init` payload,
+/// and an API key as `X-API-Key`. The session is checked again before every item a
+/// subscription emits, see `subscription::while_authorized`.
+pub struct SubscriptionEndpoint<E> {
+    executor: E,
+    auth: Auth,
+}
+
+impl<E: Executor> poem::Endpoint for SubscriptionEndpoint<E> {
+    type Output = poem::Response;
+
+    async fn call(&self, req: poem::Request) -> poem::Result<poem::Response> {
+        let session = req.extensions().get::<Session>().cloned();
+        let (req, mut body) = req.split();
+        let websocket = WebSocket::from_request(&req, &mut body).await?;
+        let protocol = GraphQLProtocol::from_request(&req, &mut body).await?;
+        let executor = self.executor.clone();
+        let auth = self.auth.clone();
+
+        let resp = websocket
+            .protocols(ALL_WEBSOCKET_PROTOCOLS)
+            .on_upgrade(move |stream| {
+                GraphQLWebSocket::new(stream, executor, protocol)
+                    .on_connection_init(move |payload| async move {
+                        let mut data = Data::default();
+                        let api_key = payload.get("X-API-Key").and_then(|value| value.as_str());
+                        let token = payload
+                            .get("Authorization")
+                            .and_then(|value| value.as_str())
+                            .map(|value| value.strip_prefix("Bearer ").unwrap_or(value));
+                        let init_session = match (api_key, token) {
+                            (Some(api_key), _) => Some(auth.authenticate_api_key(api_key)?),
+                            (None, Some(token)) => Some(auth.authenticate(token)?),
+                            (None, None) => None,
+                        };
+                        match init_session {
+                            Some(Some(session)) => data.insert(session),
+                            Some(None) => return Err("Invalid or expired token".into()),
+                            None => {
+                                if let Some(session) = session {
+                                    data.insert(session);
+                                }
+                            }
+                        }
+                        Ok(data)
+                    })
+                    .serve()
+            })
+            .into_response();
+        Ok(resp)
+    }
+}
+
+pub fn create_subscription_endpoint<Q, M, S>(
+    schema: Schema<Q, M, S>,
+    auth: Auth,
+) -> SubscriptionEndpoint<Schema<Q, M, S>> {
+    SubscriptionEndpoint {
+        executor: schema,
+        auth,
+    }
 }
 
 pub struct SdlEndpoint<Q, M, S>(Schema<Q, M, S>);
@@ -0,0 +1,193 @@
+use async_graphql::futures_util::{Stream, StreamExt, future, stream};
+use async_graphql::*;
+use log::*;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::alarms::FMSAlarmEvent;
+use crate::auth::{Auth, Scope, Session};
+use crate::field::{Field, FieldUpdate};
+use crate::graph::guards::ScopeGuard;
+use crate::graph::inputs::*;
+use crate::graph::types::*;
+
+pub struct Subscription;
+
+/// Yields every message sent on a broadcast channel, skipping over messages missed by a slow
+/// client.
+fn broadcast_stream<T: Clone + Send + 'static>(
+    receiver: broadcast::Receiver<T>,
+) -> impl Stream<Item = T> {
+    stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(message) => return Some((message, receiver)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+/// Yields every `FieldUpdate` matching `filter`
+fn field_updates(
+    field: &Field,
+    filter: fn(FieldUpdate) -> bool,
+) -> impl Stream<Item = FieldUpdate> + use<> {
+    broadcast_stream(field.subscribe()).filter(move |update| future::ready(filter(*update)))
+}
+
+/// Ends a subscription at its next item once the session that started it has been signed out,
+/// has expired, was revoked or has lost `scope`
+fn while_authorized<S: Stream>(
+    ctx: &Context<'_>,
+    scope: Scope,
+    stream: S,
+) -> impl Stream<Item = S::Item> + use<S> {
+    let auth = ctx.data::<Auth>().unwrap().clone();
+    let session = ctx.data_opt::<Session>().cloned();
+    stream.take_while(move |_| {
+        let refreshed = session
+            .as_ref()
+            .map(|session| auth.refresh_session(session));
+        let authorized = match refreshed {
+            Some(Ok(Some(session))) => session.has_scope(scope),
+            Some(Err(e)) => {
+                error!("Error checking the session of a subscription: {}", e);
+                false
+            }
+            _ => false,
+        };
+        future::ready(authorized)
+    })
+}
+
+#[Subscription]
+impl Subscription {
+    /// Emits the field state once on subscription and again every time it changes
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
+    async fn field_state(&self, ctx: &Context<'_>) -> impl Stream<Item = GQLFieldState> {
+        let field = ctx.data::<Field>().unwrap().clone();
+        let updates = field_updates(&field, |update| update == FieldUpdate::FieldState);
+        let states = stream::once(async {})
+            .chain(updates.map(|_| ()))
+            .map(move |_| GQLFieldState {
+                obj_field: field.clone(),
+            });
+        while_authorized(ctx, Scope::Readonly, states)
+    }
+
+    /// Emits every driver station once on subscription and again every time any of them are
+    /// added, removed or changed
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
+    async fn driver_stations(
+        &self,
+        ctx: &Context<'_>,
+    ) -> impl Stream<Item = Vec<GQLDriverStation>> {
+        let field = ctx.data::<Field>().unwrap().clone();
+        let updates = field_updates(&field, |update| {
+            matches!(update, FieldUpdate::DriverStation { .. })
+        });
+        let driverstations = stream::once(async {})
+            .chain(updates.map(|_| ()))
+            .map(move |_| {
+                field
+                    .driverstations()
+                    .get_all_driverstations()
+                    .iter()
+                    .map(|ds| GQLDriverStation {
+                        obj_driverstation: ds.clone(),
+                    })
+                    .collect()
+            });
+        while_authorized(ctx, Scope::Readonly, driverstations)
+    }
+
+    /// Emits every alarm as it is thrown, released or cleared
+    #[graphql(name = "fmsAlarmEvents", guard = "ScopeGuard(Scope::Readonly)")]
+    async fn fms_alarm_events(&self, ctx: &Context<'_>) -> impl Stream<Item = GQLFMSAlarmEvent> {
+        let field = ctx.data::<Field>().unwrap();
+        let events =
+            broadcast_stream(field.alarm_handler().subscribe()).map(|event: FMSAlarmEvent| {
+                GQLFMSAlarmEvent {
+                    obj_fmsalarmevent: event,
+                }
+            });
+        while_authorized(ctx, Scope::Readonly, events)
+    }
+
+    /// Emits a driver station every time its connection, confirmed state or settings change
+    #[graphql(guard = "ScopeGuard(Scope::Readonly)")]
+    async fn driver_station_updated(
+        &self,
+        ctx: &Context<'_>,
+        criteria: Option<GQLDriverStationByCriteriaInput>,
+    ) -> impl Stream<Item = GQLDriverStation> {
+        let field = ctx.data::<Field>().unwrap().clone();
+        let updates = field_updates(&field, |update| {
+            matches!(update, FieldUpdate::DriverStation { .. })
+        });
+        let updates = updates.filter_map(move |update| {
+            let driverstations = field.driverstations();
+            let ds = match (&criteria, update) {
+                (Some(criteria), _) => criteria.find(&driverstations).filter(|ds| {
+                    update
+                        == FieldUpdate::DriverStation {
+                            team_number: ds.team_number(),
+                        }
+                }),
+                (None, FieldUpdate::DriverStation { team_number }) => {
+                    driverstations.get_driverstation_by_team_number(team_number)
+                }
+                (None, _) => None,
+            };
+            async move {
+                ds.map(|ds| GQLDriverStation {
+                    obj_driverstation: ds,
+                })
+            }
+        });
+        while_authorized(ctx, Scope::Readonly, updates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, time::Duration};
+
+    use super::*;
+    use crate::{
+        auth::AuthConfig, database::Database, field::timing::MatchTimings,
+        graph::schema::create_schema,
+    };
+
+    #[tokio::test]
+    async fn subscription_ends_when_its_api_key_is_revoked() {
+        let database = Database::open(Path::new(":memory:")).unwrap();
+        let auth = Auth::new(database.clone(), AuthConfig::default());
+        let field = Field::new(MatchTimings::default(), database).unwrap();
+        let schema = create_schema(field.clone(), auth.clone());
+
+        let new_api_key = auth
+            .create_api_key("overlay", &[Scope::Readonly], "test")
+            .unwrap();
+        let session = auth
+            .authenticate_api_key(&new_api_key.value)
+            .unwrap()
+            .unwrap();
+        let request = Request::new("subscription { fieldState { eventName } }").data(session);
+        let mut responses = schema.execute_stream(request);
+        let next = async |responses: &mut _| {
+            tokio::time::timeout(Duration::from_secs(1), StreamExt::next(responses))
+                .await
+                .unwrap()
+        };
+
+        assert!(next(&mut responses).await.unwrap().is_ok());
+        field.set_event_name("2026test".to_string()).unwrap();
+        assert!(next(&mut responses).await.unwrap().is_ok());
+
+        auth.revoke_api_key(new_api_key.api_key.id, "test").unwrap();
+        field.set_event_name("2026other".to_string()).unwrap();
+        assert!(next(&mut responses).await.is_none());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::auth::{ApiKey, NewApiKey};
+use crate::graph::types::GQLScope;
+use async_graphql::*;
+
+pub struct GQLApiKey {
+    pub obj_api_key: ApiKey,
+}
+
+#[Object(name = "ApiKey")]
+impl GQLApiKey {
+    async fn id(&self) -> ID {
+        ID(self.obj_api_key.id.to_string())
+    }
+
+    async fn name(&self) -> String {
+        self.obj_api_key.name.clone()
+    }
+
+    async fn scopes(&self) -> Vec<GQLScope> {
+        self.obj_api_key
+            .scopes
+            .iter()
+            .map(|scope| (*scope).into())
+            .collect()
+    }
+
+    async fn created_by(&self) -> String {
+        self.obj_api_key.created_by.clone()
+    }
+
+    async fn created_at(&self) -> i64 {
+        self.obj_api_key.created_at
+    }
+
+    async fn last_used_at(&self) -> Option<i64> {
+        self.obj_api_key.last_used_at
+    }
+
+    async fn revoked_at(&self) -> Option<i64> {
+        self.obj_api_key.revoked_at
+    }
+}
+
+pub struct GQLNewApiKey {
+    pub obj_new_api_key: NewApiKey,
+}
+
+#[Object(name = "NewApiKey")]
+impl GQLNewApiKey {
+    async fn api_key(&self) -> GQLApiKey {
+        GQLApiKey {
+            obj_api_key: self.obj_new_api_key.api_key.clone(),
+        }
+    }
+
+    /// Send this as the `X-API-Key` header. It cannot be retrieved again.
+    async fn key_value(&self) -> String {
+        self.obj_new_api_key.value.clone()
+    }
+}
@@ -0,0 +1,48 @@
+use crate::database::AuditEntry;
+use async_graphql::*;
+
+pub struct GQLAuditEntry {
+    pub obj_auditentry: AuditEntry,
+}
+
+#[Object(name = "AuditEntry")]
+impl GQLAuditEntry {
+    async fn id(&self) -> ID {
+        ID(self.obj_auditentry.id.to_string())
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.obj_auditentry.timestamp
+    }
+
+    /// The username, `api-key:<name>` or `fms` for actions the FMS took on its own
+    async fn actor(&self) -> String {
+        self.obj_auditentry.actor.clone()
+    }
+
+    async fn operation(&self) -> String {
+        self.obj_auditentry.operation.clone()
+    }
+
+    async fn arguments(&self) -> Json<serde_json::Value> {
+        Json(parse_json(&self.obj_auditentry.arguments))
+    }
+
+    async fn previous_value(&self) -> Option<Json<serde_json::Value>> {
+        self.obj_auditentry
+            .previous_value
+            .as_deref()
+            .map(|value| Json(parse_json(value)))
+    }
+
+    async fn new_value(&self) -> Option<Json<serde_json::Value>> {
+        self.obj_auditentry
+            .new_value
+            .as_deref()
+            .map(|value| Json(parse_json(value)))
+    }
+}
+
+fn parse_json(value: &str) -> serde_json::Value {
+    serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.to_string()))
+}
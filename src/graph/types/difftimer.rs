@@ -0,0 +1,32 @@
+use async_graphql::*;
+use chrono::Utc;
+
+use crate::difftimer::DiffTimer;
+
+pub struct GQLDiffTimer {
+    pub obj_difftimer: DiffTimer,
+}
+
+#[Object(name = "DiffTimer")]
+impl GQLDiffTimer {
+    /// Unix time in milliseconds at which the timer was started, or null if it is frozen
+    async fn started_at_millis(&self) -> Option<i64> {
+        self.obj_difftimer.started_at().map(|started_at| {
+            let elapsed = chrono::Duration::from_std(started_at.elapsed()).unwrap_or_default();
+            (Utc::now() - elapsed).timestamp_millis()
+        })
+    }
+
+    /// Seconds remaining at `startedAtMillis`, or the frozen time if the timer is stopped
+    async fn time_remaining(&self) -> f64 {
+        self.obj_difftimer.time_remaining().as_secs_f64()
+    }
+
+    async fn current_time_remaining(&self) -> f64 {
+        self.obj_difftimer.current_time_remaining().as_secs_f64()
+    }
+
+    async fn is_running(&self) -> bool {
+        self.obj_difftimer.is_running()
+    }
+}
@@ -1,8 +1,17 @@
+#![allow(clippy::unused_async)]
+
+use crate::database::TelemetryFilter;
 use crate::field::connection::DriverStationConnection;
 use crate::field::driverstation::{
-    DriverStation, DriverStationConfirmedState, DriverStationLogData, DriverStationLogMessage,
+    DriverStation, DriverStationConfirmedState, DriverStationControlEvent, DriverStationLogData,
+    DriverStationLogMessage,
 };
 use crate::field::enums::VersionData;
+use crate::field::packetstats::UdpPacketStats;
+use crate::field::{
+    protocol::{CommsMetrics, LaptopMetrics, RadioMetrics},
+    tags::DriverStationDiagnostics,
+};
 use crate::graph::types::*;
 use async_graphql::*;
 
@@ -28,6 +37,28 @@ impl GQLDriverStation {
         self.obj_driverstation.enabled()
     }
 
+    async fn emergency_stopped(&self) -> bool {
+        self.obj_driverstation.emergency_stopped()
+    }
+
+    async fn autonomous_stopped(&self) -> bool {
+        self.obj_driverstation.autonomous_stopped()
+    }
+
+    async fn manually_disabled(&self) -> bool {
+        self.obj_driverstation.manually_disabled()
+    }
+
+    async fn control_history(&self) -> Vec<GQLDriverStationControlEvent> {
+        self.obj_driverstation
+            .control_history()
+            .iter()
+            .map(|control_event| GQLDriverStationControlEvent {
+                obj_driverstationcontrolevent: control_event.clone(),
+            })
+            .collect()
+    }
+
     async fn expected_ip(&self) -> Option<GQLIpCidr> {
         self.obj_driverstation.expected_ip().map(GQLIpCidr)
     }
@@ -46,24 +77,58 @@ impl GQLDriverStation {
             })
     }
 
-    async fn log_data(&self) -> Vec<GQLDriverStationLogData> {
+    async fn diagnostics(&self) -> Option<GQLDriverStationDiagnostics> {
         self.obj_driverstation
-            .log_data()
-            .iter()
-            .map(|log_data| GQLDriverStationLogData {
-                obj_driverstationlogdata: log_data.clone(),
+            .diagnostics()
+            .map(|diagnostics| GQLDriverStationDiagnostics {
+                obj_driverstationdiagnostics: diagnostics,
             })
-            .collect()
     }
 
-    async fn log_messages(&self) -> Vec<GQLDriverStationLogMessage> {
-        self.obj_driverstation
-            .log_messages()
-            .iter()
+    async fn log_data(
+        &self,
+        since: Option<u64>,
+        until: Option<u64>,
+        match_id: Option<i64>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Vec<GQLDriverStationLogData>> {
+        let filter = TelemetryFilter {
+            since,
+            until,
+            match_id,
+            limit,
+        };
+        Ok(self
+            .obj_driverstation
+            .log_data(&filter)?
+            .into_iter()
+            .map(|log_data| GQLDriverStationLogData {
+                obj_driverstationlogdata: log_data,
+            })
+            .collect())
+    }
+
+    async fn log_messages(
+        &self,
+        since: Option<u64>,
+        until: Option<u64>,
+        match_id: Option<i64>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<Vec<GQLDriverStationLogMessage>> {
+        let filter = TelemetryFilter {
+            since,
+            until,
+            match_id,
+            limit,
+        };
+        Ok(self
+            .obj_driverstation
+            .log_messages(&filter)?
+            .into_iter()
             .map(|log_message| GQLDriverStationLogMessage {
-                obj_driverstationlogmessage: log_message.clone(),
+                obj_driverstationlogmessage: log_message,
             })
-            .collect()
+            .collect())
     }
 
     async fn versions(&self) -> Vec<GQLVersionData> {
@@ -101,6 +166,60 @@ impl GQLDriverStationConnection {
             .last_udp_packet_reception()
             .timestamp_millis()
     }
+
+    async fn udp_packet_stats(&self) -> GQLUdpPacketStats {
+        GQLUdpPacketStats {
+            obj_udppacketstats: self.obj_driverstationconnection.udp_packet_stats(),
+        }
+    }
+}
+
+pub struct GQLUdpPacketStats {
+    pub obj_udppacketstats: UdpPacketStats,
+}
+
+/// Sequence number tracking of the UDP packets received from a driver station. Loss here is
+/// between the driver station and the FMS, while `lostPackets` in the log data is between the
+/// driver station and the robot.
+#[Object(name = "UdpPacketStats")]
+impl GQLUdpPacketStats {
+    async fn last_sequence_number(&self) -> Option<u16> {
+        self.obj_udppacketstats.last_sequence_num()
+    }
+
+    async fn packets_received(&self) -> u64 {
+        self.obj_udppacketstats.packets_received()
+    }
+
+    async fn packets_lost(&self) -> u64 {
+        self.obj_udppacketstats.packets_lost()
+    }
+
+    async fn packets_out_of_order(&self) -> u64 {
+        self.obj_udppacketstats.packets_out_of_order()
+    }
+
+    async fn packets_duplicated(&self) -> u64 {
+        self.obj_udppacketstats.packets_duplicated()
+    }
+
+    async fn sequence_wraparounds(&self) -> u64 {
+        self.obj_udppacketstats.sequence_wraparounds()
+    }
+
+    async fn sequence_resyncs(&self) -> u64 {
+        self.obj_udppacketstats.sequence_resyncs()
+    }
+
+    /// Packet loss over the last ten seconds
+    async fn recent_packet_loss_percent(&self) -> f64 {
+        self.obj_udppacketstats
+            .recent_packet_loss_percent(std::time::Instant::now())
+    }
+
+    async fn jitter_millis(&self) -> f64 {
+        self.obj_udppacketstats.jitter().as_secs_f64() * 1000.0
+    }
 }
 
 pub struct GQLDriverStationConfirmedState {
@@ -143,6 +262,134 @@ impl GQLDriverStationConfirmedState {
     }
 }
 
+pub struct GQLDriverStationDiagnostics {
+    pub obj_driverstationdiagnostics: DriverStationDiagnostics,
+}
+
+/// The latest value of each tag the driver station appends to its UDP packets
+#[Object(name = "DriverStationDiagnostics")]
+impl GQLDriverStationDiagnostics {
+    async fn field_radio(&self) -> Option<GQLRadioMetrics> {
+        self.obj_driverstationdiagnostics
+            .field_radio
+            .map(|obj_radiometrics| GQLRadioMetrics { obj_radiometrics })
+    }
+
+    async fn comms(&self) -> Option<GQLCommsMetrics> {
+        self.obj_driverstationdiagnostics
+            .comms
+            .map(|obj_commsmetrics| GQLCommsMetrics { obj_commsmetrics })
+    }
+
+    async fn laptop(&self) -> Option<GQLLaptopMetrics> {
+        self.obj_driverstationdiagnostics
+            .laptop
+            .map(|obj_laptopmetrics| GQLLaptopMetrics { obj_laptopmetrics })
+    }
+
+    async fn robot_radio(&self) -> Option<GQLRadioMetrics> {
+        self.obj_driverstationdiagnostics
+            .robot_radio
+            .map(|obj_radiometrics| GQLRadioMetrics { obj_radiometrics })
+    }
+
+    async fn unknown_tags(&self) -> Vec<GQLUnknownTag> {
+        self.obj_driverstationdiagnostics
+            .unknown_tags
+            .iter()
+            .map(|(id, data)| GQLUnknownTag {
+                id: *id,
+                data: data.clone(),
+            })
+            .collect()
+    }
+}
+
+pub struct GQLRadioMetrics {
+    pub obj_radiometrics: RadioMetrics,
+}
+
+#[Object(name = "RadioMetrics")]
+impl GQLRadioMetrics {
+    async fn signal_strength(&self) -> u8 {
+        self.obj_radiometrics.signal_strength
+    }
+
+    async fn bandwidth_utilization(&self) -> u16 {
+        self.obj_radiometrics.bandwidth_utilization
+    }
+}
+
+pub struct GQLCommsMetrics {
+    pub obj_commsmetrics: CommsMetrics,
+}
+
+#[Object(name = "CommsMetrics")]
+impl GQLCommsMetrics {
+    async fn lost_packets(&self) -> u16 {
+        self.obj_commsmetrics.lost_packets
+    }
+
+    async fn sent_packets(&self) -> u16 {
+        self.obj_commsmetrics.sent_packets
+    }
+
+    async fn average_trip_time(&self) -> u8 {
+        self.obj_commsmetrics.average_trip_time
+    }
+}
+
+pub struct GQLLaptopMetrics {
+    pub obj_laptopmetrics: LaptopMetrics,
+}
+
+#[Object(name = "LaptopMetrics")]
+impl GQLLaptopMetrics {
+    async fn battery_percent(&self) -> u8 {
+        self.obj_laptopmetrics.battery_percent
+    }
+
+    async fn cpu_percent(&self) -> u8 {
+        self.obj_laptopmetrics.cpu_percent
+    }
+}
+
+pub struct GQLUnknownTag {
+    pub id: u8,
+    pub data: Vec<u8>,
+}
+
+/// A tag the FMS could not decode, kept as it was received
+#[Object(name = "UnknownTag")]
+impl GQLUnknownTag {
+    async fn id(&self) -> u8 {
+        self.id
+    }
+
+    async fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+pub struct GQLDriverStationControlEvent {
+    pub obj_driverstationcontrolevent: DriverStationControlEvent,
+}
+
+#[Object(name = "DriverStationControlEvent")]
+impl GQLDriverStationControlEvent {
+    async fn control(&self) -> GQLDriverStationControl {
+        self.obj_driverstationcontrolevent.control.into()
+    }
+
+    async fn triggered_by(&self) -> String {
+        self.obj_driverstationcontrolevent.triggered_by.clone()
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.obj_driverstationcontrolevent.timestamp
+    }
+}
+
 pub struct GQLDriverStationLogData {
     pub obj_driverstationlogdata: DriverStationLogData,
 }
@@ -20,6 +20,56 @@ pub enum GQLMode {
     Autonomous,
 }
 
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(remote = "crate::field::enums::MatchState", name = "MatchState")]
+pub enum GQLMatchState {
+    Idle,
+    PreStart,
+    Auto,
+    Transition,
+    Teleop,
+    PostMatch,
+    Aborted,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(
+    remote = "crate::field::gamedata::GameDataRelease",
+    name = "GameDataRelease"
+)]
+pub enum GQLGameDataRelease {
+    PreStart,
+    Auto,
+    Teleop,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(
+    remote = "crate::field::prestart::PreStartFailure",
+    name = "PreStartFailure"
+)]
+pub enum GQLPreStartFailure {
+    FieldSafe,
+    FieldFaulted,
+    NotConnected,
+    NoRobotComms,
+    NoRioPing,
+    WrongStation,
+    StationFaulted,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(
+    remote = "crate::field::enums::DriverStationControl",
+    name = "DriverStationControl"
+)]
+pub enum GQLDriverStationControl {
+    Enable,
+    Disable,
+    EmergencyStop,
+    AutonomousStop,
+}
+
 #[derive(Enum, Copy, Clone, Eq, PartialEq)]
 #[graphql(
     remote = "crate::field::enums::AllianceStation",
@@ -51,6 +101,17 @@ pub enum GQLVersionType {
     ThirdParty
 }
 
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(
+    remote = "crate::alarms::FMSAlarmEventType",
+    name = "FMSAlarmEventType"
+)]
+pub enum GQLFMSAlarmEventType {
+    Thrown,
+    Released,
+    Cleared,
+}
+
 #[derive(Enum, Copy, Clone, Eq, PartialEq)]
 #[graphql(
     remote = "crate::alarms::FMSAlarmType",
@@ -62,3 +123,18 @@ pub enum GQLFMSAlarmType {
     Fault,
 }
 
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(
+    remote = "crate::auth::Scope",
+    name = "Scope"
+)]
+pub enum GQLScope {
+    Readonly,
+    FieldControl,
+    FieldOverride,
+    AlarmsClear,
+    DsAssign,
+    ScheduleEdit,
+    UsersManage,
+}
@@ -1,5 +1,7 @@
-use crate::alarms::FMSAlarm;
+use crate::alarms::{FMSAlarm, FMSAlarmEvent};
 use crate::field::Field;
+use crate::field::enums::AllianceStation;
+use crate::field::gamedata::GameData;
 use crate::graph::types::*;
 use async_graphql::*;
 
@@ -33,6 +35,36 @@ impl GQLFieldState {
             .as_secs_f64()
     }
 
+    async fn timer(&self) -> GQLDiffTimer {
+        GQLDiffTimer {
+            obj_difftimer: self.obj_field.timer(),
+        }
+    }
+
+    async fn match_state(&self) -> GQLMatchState {
+        self.obj_field.match_state().into()
+    }
+
+    async fn match_paused(&self) -> bool {
+        self.obj_field.match_paused()
+    }
+
+    async fn is_endgame(&self) -> bool {
+        self.obj_field.is_endgame()
+    }
+
+    async fn timing_profile(&self) -> GQLMatchTimingProfile {
+        let tournament_level = self.obj_field.tournament_level();
+        GQLMatchTimingProfile {
+            name: self
+                .obj_field
+                .timings()
+                .profile_name(tournament_level)
+                .to_string(),
+            obj_matchtimingprofile: self.obj_field.timing_profile(),
+        }
+    }
+
     async fn ds_mode(&self) -> GQLMode {
         self.obj_field.ds_mode().into()
     }
@@ -48,6 +80,46 @@ impl GQLFieldState {
     async fn tcp_online(&self) -> bool {
         self.obj_field.tcp_online()
     }
+
+    async fn game_data(&self) -> Vec<GQLStationGameData> {
+        let match_state = self.obj_field.match_state();
+        let mut game_data: Vec<_> = self.obj_field.game_data().into_iter().collect();
+        game_data.sort_by_key(|(alliance_station, _)| alliance_station.to_byte());
+        game_data
+            .into_iter()
+            .map(|(alliance_station, game_data)| GQLStationGameData {
+                alliance_station,
+                released: game_data.release.is_released(match_state),
+                obj_gamedata: game_data,
+            })
+            .collect()
+    }
+}
+
+pub struct GQLStationGameData {
+    pub alliance_station: AllianceStation,
+    pub obj_gamedata: GameData,
+    pub released: bool,
+}
+
+#[Object(name = "StationGameData")]
+impl GQLStationGameData {
+    async fn alliance_station(&self) -> GQLAllianceStation {
+        self.alliance_station.into()
+    }
+
+    async fn message(&self) -> String {
+        self.obj_gamedata.message.clone()
+    }
+
+    async fn release(&self) -> GQLGameDataRelease {
+        self.obj_gamedata.release.into()
+    }
+
+    /// Whether driver stations are currently being sent this message
+    async fn released(&self) -> bool {
+        self.released
+    }
 }
 
 pub struct GQLFMSAlarm {
@@ -92,3 +164,20 @@ impl GQLFMSAlarm {
         self.obj_fmsalarm.auto_clear
     }
 }
+
+pub struct GQLFMSAlarmEvent {
+    pub obj_fmsalarmevent: FMSAlarmEvent,
+}
+
+#[Object(name = "FMSAlarmEvent")]
+impl GQLFMSAlarmEvent {
+    async fn event_type(&self) -> GQLFMSAlarmEventType {
+        self.obj_fmsalarmevent.event_type.into()
+    }
+
+    async fn alarm(&self) -> GQLFMSAlarm {
+        GQLFMSAlarm {
+            obj_fmsalarm: self.obj_fmsalarmevent.alarm.clone(),
+        }
+    }
+}
@@ -1,13 +1,25 @@
+pub mod apikey;
+pub mod audit;
+pub mod difftimer;
 pub mod driverstation;
 pub mod enums;
 pub mod fieldmatch;
 pub mod fieldstate;
 pub mod ipaddr;
 pub mod ipcidr;
+pub mod prestart;
+pub mod timing;
+pub mod user;
 
+pub use apikey::*;
+pub use audit::*;
+pub use difftimer::*;
 pub use driverstation::*;
 pub use enums::*;
 pub use fieldmatch::*;
 pub use fieldstate::*;
 pub use ipaddr::*;
 pub use ipcidr::*;
+pub use prestart::*;
+pub use timing::*;
+pub use user::*;
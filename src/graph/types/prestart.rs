@@ -0,0 +1,60 @@
+use crate::field::prestart::{PreStartCheck, PreStartStationCheck};
+use crate::graph::types::*;
+use async_graphql::*;
+
+pub struct GQLPreStartCheck {
+    pub obj_prestartcheck: PreStartCheck,
+}
+
+#[Object(name = "PreStartCheck")]
+impl GQLPreStartCheck {
+    async fn ready(&self) -> bool {
+        self.obj_prestartcheck.is_ready()
+    }
+
+    async fn field_failures(&self) -> Vec<GQLPreStartFailure> {
+        self.obj_prestartcheck
+            .field_failures
+            .iter()
+            .map(|failure| (*failure).into())
+            .collect()
+    }
+
+    async fn stations(&self) -> Vec<GQLPreStartStationCheck> {
+        self.obj_prestartcheck
+            .stations
+            .iter()
+            .cloned()
+            .map(|station| GQLPreStartStationCheck {
+                obj_prestartstationcheck: station,
+            })
+            .collect()
+    }
+}
+
+pub struct GQLPreStartStationCheck {
+    pub obj_prestartstationcheck: PreStartStationCheck,
+}
+
+#[Object(name = "PreStartStationCheck")]
+impl GQLPreStartStationCheck {
+    async fn alliance_station(&self) -> GQLAllianceStation {
+        self.obj_prestartstationcheck.alliance_station.into()
+    }
+
+    async fn team_number(&self) -> Option<u16> {
+        self.obj_prestartstationcheck.team_number
+    }
+
+    async fn ready(&self) -> bool {
+        self.obj_prestartstationcheck.failures.is_empty()
+    }
+
+    async fn failures(&self) -> Vec<GQLPreStartFailure> {
+        self.obj_prestartstationcheck
+            .failures
+            .iter()
+            .map(|failure| (*failure).into())
+            .collect()
+    }
+}
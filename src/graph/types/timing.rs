@@ -0,0 +1,34 @@
+use crate::field::timing::MatchTimingProfile;
+use async_graphql::*;
+
+pub struct GQLMatchTimingProfile {
+    pub name: String,
+    pub obj_matchtimingprofile: MatchTimingProfile,
+}
+
+#[Object(name = "MatchTimingProfile")]
+impl GQLMatchTimingProfile {
+    async fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn auto_secs(&self) -> u64 {
+        self.obj_matchtimingprofile.auto_secs
+    }
+
+    async fn transition_secs(&self) -> u64 {
+        self.obj_matchtimingprofile.transition_secs
+    }
+
+    async fn teleop_secs(&self) -> u64 {
+        self.obj_matchtimingprofile.teleop_secs
+    }
+
+    async fn endgame_warning_secs(&self) -> u64 {
+        self.obj_matchtimingprofile.endgame_warning_secs
+    }
+
+    async fn post_match_hold_secs(&self) -> u64 {
+        self.obj_matchtimingprofile.post_match_hold_secs
+    }
+}
@@ -0,0 +1,53 @@
+use crate::auth::{Token, User};
+use crate::graph::types::GQLScope;
+use async_graphql::*;
+
+pub struct GQLUser {
+    pub obj_user: User,
+}
+
+#[Object(name = "User")]
+impl GQLUser {
+    async fn id(&self) -> ID {
+        ID(self.obj_user.id.to_string())
+    }
+
+    async fn name(&self) -> String {
+        self.obj_user.name.clone()
+    }
+
+    async fn username(&self) -> String {
+        self.obj_user.username.clone()
+    }
+
+    async fn scopes(&self) -> Vec<GQLScope> {
+        self.obj_user
+            .scopes
+            .iter()
+            .map(|scope| (*scope).into())
+            .collect()
+    }
+}
+
+pub struct GQLToken {
+    pub obj_token: Token,
+}
+
+#[Object(name = "Token")]
+impl GQLToken {
+    async fn expires_at(&self) -> i64 {
+        self.obj_token.expires_at
+    }
+
+    async fn scope(&self) -> Vec<String> {
+        self.obj_token
+            .scopes
+            .iter()
+            .map(|scope| scope.to_string())
+            .collect()
+    }
+
+    async fn token_value(&self) -> String {
+        self.obj_token.value.clone()
+    }
+}
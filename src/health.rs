@@ -0,0 +1,96 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::field::Field;
+
+/// How long a tick loop may go without finishing a tick before it counts as stalled
+const TICK_LOOP_STALL_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ComponentHealth {
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ComponentHealth {
+    fn healthy() -> Self {
+        Self {
+            healthy: true,
+            detail: None,
+        }
+    }
+
+    fn unhealthy(detail: impl Into<String>) -> Self {
+        Self {
+            healthy: false,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+/// The state of every FMS subsystem, served at `/healthz` and `/readyz`.
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthReport {
+    pub udp_listener: ComponentHealth,
+    pub tcp_listener: ComponentHealth,
+    pub field_tick_loop: ComponentHealth,
+    pub driverstations_tick_loop: ComponentHealth,
+    pub database: ComponentHealth,
+}
+
+impl HealthReport {
+    // Public API -->
+
+    pub fn check(field: &Field) -> Self {
+        let udp_listener = if field.udp_online() {
+            ComponentHealth::healthy()
+        } else {
+            ComponentHealth::unhealthy("Not bound to the driver station UDP port")
+        };
+        let tcp_listener = if field.tcp_online() {
+            ComponentHealth::healthy()
+        } else {
+            ComponentHealth::unhealthy("Not bound to the driver station TCP port")
+        };
+        let database = match field.database().check() {
+            Ok(()) => ComponentHealth::healthy(),
+            Err(e) => ComponentHealth::unhealthy(e.to_string()),
+        };
+
+        Self {
+            udp_listener,
+            tcp_listener,
+            field_tick_loop: tick_loop_health(field.last_tick_at()),
+            driverstations_tick_loop: tick_loop_health(field.driverstations().last_tick_at()),
+            database,
+        }
+    }
+
+    /// Whether the FMS is running at all, which only needs both tick loops to keep ticking
+    pub fn is_live(&self) -> bool {
+        self.field_tick_loop.healthy && self.driverstations_tick_loop.healthy
+    }
+
+    /// Whether the field can talk to driver stations and keep records of the event
+    pub fn is_ready(&self) -> bool {
+        self.is_live()
+            && self.udp_listener.healthy
+            && self.tcp_listener.healthy
+            && self.database.healthy
+    }
+}
+
+fn tick_loop_health(last_tick_at: Option<Instant>) -> ComponentHealth {
+    match last_tick_at {
+        Some(last_tick_at) if last_tick_at.elapsed() < TICK_LOOP_STALL_TIMEOUT => {
+            ComponentHealth::healthy()
+        }
+        Some(last_tick_at) => ComponentHealth::unhealthy(format!(
+            "Last tick finished {:.1}s ago",
+            last_tick_at.elapsed().as_secs_f32()
+        )),
+        None => ComponentHealth::unhealthy("Not started yet"),
+    }
+}
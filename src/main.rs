@@ -1,7 +1,13 @@
 pub mod alarms;
+pub mod audit;
+pub mod auth;
+pub mod config;
+pub mod database;
 pub mod difftimer;
 pub mod field;
 pub mod graph;
+pub mod health;
+pub mod metrics;
 pub mod web;
 // TODO These do not need to be pub
 
@@ -10,10 +16,16 @@ use log::*;
 use std::{
     env,
     net::{IpAddr, SocketAddr},
+    path::PathBuf,
 };
 use tokio_util::sync::CancellationToken;
 
-use crate::field::Field;
+use crate::{
+    auth::{Auth, oidc::Oidc},
+    config::Config,
+    database::Database,
+    field::Field,
+};
 
 const NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -37,6 +49,27 @@ struct Cli {
     #[clap(long, default_value = "0.0.0.0:8000", env = "NEVERMORE_WEB_ADDRESS")]
     web_address: SocketAddr,
 
+    /// Sets the path of the TOML config file containing match timing profiles.
+    #[clap(long, env = "NEVERMORE_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Sets the path of the SQLite database the event is stored in.
+    #[clap(long, default_value = "nevermore.sqlite3", env = "NEVERMORE_DATABASE")]
+    database: PathBuf,
+
+    /// Sets the password of the `admin` user created when the database has no users yet.
+    #[clap(long, env = "NEVERMORE_ADMIN_PASSWORD", hide_env_values = true)]
+    admin_password: Option<String>,
+
+    /// Serves web UI files from this directory in place of the ones built into the FMS.
+    #[clap(long, env = "NEVERMORE_UI_DIR")]
+    ui_dir: Option<PathBuf>,
+
+    /// Serves a GraphiQL-style query editor at /api/graphiql to clients with an API key, passed
+    /// as `?key=` when opening the page in a browser.
+    #[clap(long, env = "NEVERMORE_GRAPHIQL")]
+    graphiql: bool,
+
     #[clap(short, long)]
     tray: bool,
 
@@ -65,13 +98,34 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Starting {} v{} by {}...", NAME, VERSION, AUTHORS);
 
-    let field = Field::new();
+    let config = Config::load(cli.config.as_deref())?;
+
+    let database = Database::open(&cli.database)?;
+
+    let auth = Auth::new(database.clone(), config.auth.clone());
+    auth.bootstrap(cli.admin_password.as_deref())?;
+    auth.prune_sessions()?;
+    let oidc = config
+        .auth
+        .oidc
+        .map(|oidc_config| Oidc::new(oidc_config, auth.clone()))
+        .transpose()?;
+
+    let field = Field::new(config.timing, database)?;
 
     let cancellation_token = CancellationToken::new();
 
     let res = tokio::try_join!(
         field.run(cli.ds_address, cancellation_token.clone()),
-        web::run(cli.web_address, field.clone(), cancellation_token.clone())
+        web::run(
+            cli.web_address,
+            field.clone(),
+            auth,
+            oidc,
+            cli.ui_dir,
+            cli.graphiql,
+            cancellation_token.clone()
+        )
     );
 
     if let Err(e) = res {
@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::{
+    alarms::FMSAlarmType,
+    field::{Field, driverstation::DriverStationLogData, enums::AllianceStation},
+};
+
+const STATION_LABELS: &[&str] = &["team_number", "alliance_station"];
+
+/// Prometheus metrics describing the health of the field, served at `/metrics`.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    ds_battery_voltage: GaugeVec,
+    ds_trip_time: GaugeVec,
+    ds_lost_packets: GaugeVec,
+    ds_can_utilization: GaugeVec,
+    ds_bandwidth: GaugeVec,
+    ds_connected: IntGaugeVec,
+    ds_connections: IntGauge,
+    udp_packets_received: IntCounter,
+    udp_packets_sent: IntCounter,
+    active_alarms: IntGaugeVec,
+    tick_latency: HistogramVec,
+    graphql_request_duration: HistogramVec,
+}
+
+impl Metrics {
+    // Public API -->
+
+    pub fn new() -> anyhow::Result<Self> {
+        let registry = Registry::new_custom(Some("nevermore".to_string()), None)?;
+
+        let station_gauge = |name: &str, help: &str| -> anyhow::Result<GaugeVec> {
+            let gauge = GaugeVec::new(Opts::new(name, help), STATION_LABELS)?;
+            registry.register(Box::new(gauge.clone()))?;
+            Ok(gauge)
+        };
+        let ds_battery_voltage = station_gauge(
+            "ds_battery_voltage",
+            "Robot battery voltage from the latest driver station log data",
+        )?;
+        let ds_trip_time = station_gauge(
+            "ds_trip_time_milliseconds",
+            "Round trip time between driver station and robot",
+        )?;
+        let ds_lost_packets = station_gauge(
+            "ds_lost_packets",
+            "Packets lost between driver station and robot in the latest log interval",
+        )?;
+        let ds_can_utilization =
+            station_gauge("ds_can_utilization_percent", "Robot CAN bus utilization")?;
+        let ds_bandwidth =
+            station_gauge("ds_bandwidth_megabits", "Bandwidth used by the robot radio")?;
+
+        let ds_connected = IntGaugeVec::new(
+            Opts::new(
+                "ds_connected",
+                "Whether the driver station has a live TCP connection",
+            ),
+            STATION_LABELS,
+        )?;
+        registry.register(Box::new(ds_connected.clone()))?;
+        let ds_connections = IntGauge::new(
+            "ds_connections",
+            "Driver stations with a live TCP connection",
+        )?;
+        registry.register(Box::new(ds_connections.clone()))?;
+
+        let udp_packets_received = IntCounter::new(
+            "udp_packets_received_total",
+            "UDP packets received from driver stations",
+        )?;
+        registry.register(Box::new(udp_packets_received.clone()))?;
+        let udp_packets_sent = IntCounter::new(
+            "udp_packets_sent_total",
+            "UDP control packets sent to driver stations",
+        )?;
+        registry.register(Box::new(udp_packets_sent.clone()))?;
+
+        let active_alarms = IntGaugeVec::new(
+            Opts::new("active_alarms", "Active FMS alarms"),
+            &["alarm_type"],
+        )?;
+        registry.register(Box::new(active_alarms.clone()))?;
+
+        let tick_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "tick_latency_seconds",
+                "Time from when a tick was due until it finished",
+            )
+            .buckets(vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]),
+            &["tick_loop"],
+        )?;
+        registry.register(Box::new(tick_latency.clone()))?;
+
+        let graphql_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "graphql_request_duration_seconds",
+                "Time taken to execute GraphQL queries and mutations",
+            ),
+            &["root_field", "result"],
+        )?;
+        registry.register(Box::new(graphql_request_duration.clone()))?;
+
+        Ok(Self {
+            registry,
+            ds_battery_voltage,
+            ds_trip_time,
+            ds_lost_packets,
+            ds_can_utilization,
+            ds_bandwidth,
+            ds_connected,
+            ds_connections,
+            udp_packets_received,
+            udp_packets_sent,
+            active_alarms,
+            tick_latency,
+            graphql_request_duration,
+        })
+    }
+
+    /// Renders every metric in the Prometheus text format, first refreshing the ones that are
+    /// read from the field rather than recorded as they happen
+    pub fn render(&self, field: &Field) -> anyhow::Result<String> {
+        let mut connections = 0;
+        for ds in field.driverstations().get_all_driverstations() {
+            let connected = ds
+                .active_connection()
+                .is_some_and(|connection| connection.is_alive());
+            connections += connected as i64;
+            self.ds_connected
+                .with_label_values(&station_labels(ds.team_number(), ds.alliance_station()))
+                .set(connected as i64);
+        }
+        self.ds_connections.set(connections);
+
+        let active_alarms = field.alarm_handler().active_alarms();
+        for alarm_type in [
+            FMSAlarmType::Info,
+            FMSAlarmType::Warning,
+            FMSAlarmType::Fault,
+        ] {
+            let count = active_alarms
+                .iter()
+                .filter(|alarm| alarm.alarm_type == alarm_type)
+                .count();
+            self.active_alarms
+                .with_label_values(&[format!("{:?}", alarm_type).to_lowercase()])
+                .set(count as i64);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    pub fn record_log_data(
+        &self,
+        team_number: u16,
+        alliance_station: AllianceStation,
+        log_data: &DriverStationLogData,
+    ) {
+        let labels = station_labels(team_number, alliance_station);
+        self.ds_battery_voltage
+            .with_label_values(&labels)
+            .set(log_data.voltage.into());
+        self.ds_trip_time
+            .with_label_values(&labels)
+            .set(log_data.trip_time.into());
+        self.ds_lost_packets
+            .with_label_values(&labels)
+            .set(log_data.lost_packets.into());
+        self.ds_can_utilization
+            .with_label_values(&labels)
+            .set(log_data.can_utilization.into());
+        self.ds_bandwidth
+            .with_label_values(&labels)
+            .set(log_data.bandwidth.into());
+    }
+
+    /// Drops the per-station series of a driver station that was removed from the field
+    pub fn remove_driverstation(&self, team_number: u16, alliance_station: AllianceStation) {
+        let labels = station_labels(team_number, alliance_station);
+        for gauge in [
+            &self.ds_battery_voltage,
+            &self.ds_trip_time,
+            &self.ds_lost_packets,
+            &self.ds_can_utilization,
+            &self.ds_bandwidth,
+        ] {
+            let _ = gauge.remove_label_values(&labels);
+        }
+        let _ = self.ds_connected.remove_label_values(&labels);
+    }
+
+    pub fn record_udp_packet_received(&self) {
+        self.udp_packets_received.inc();
+    }
+
+    pub fn record_udp_packet_sent(&self) {
+        self.udp_packets_sent.inc();
+    }
+
+    pub fn record_tick_latency(&self, tick_loop: &str, latency: Duration) {
+        self.tick_latency
+            .with_label_values(&[tick_loop])
+            .observe(latency.as_secs_f64());
+    }
+
+    pub fn record_graphql_request(&self, root_field: &str, succeeded: bool, duration: Duration) {
+        let result = if succeeded { "ok" } else { "error" };
+        self.graphql_request_duration
+            .with_label_values(&[root_field, result])
+            .observe(duration.as_secs_f64());
+    }
+}
+
+fn station_labels(team_number: u16, alliance_station: AllianceStation) -> [String; 2] {
+    [team_number.to_string(), alliance_station.to_string()]
+}
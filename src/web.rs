@@ -1,35 +1,92 @@
-use std::net::SocketAddr;
+mod ui;
 
-use log::info;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use log::{error, info, warn};
 use poem::{
-    EndpointExt, Route, Server, get, http::Method, listener::TcpListener, middleware::Cors, post,
+    Endpoint, EndpointExt, Request, Response, Route, Server, get, handler,
+    http::{Method, StatusCode, header},
+    listener::TcpListener,
+    middleware::Cors,
+    post,
+    web::{Data, Html, Json, Query, Redirect},
 };
+use serde::Deserialize;
 use tokio_util::sync::CancellationToken;
 
-use crate::{field::Field, graph};
+use crate::{
+    auth::{Auth, Session, oidc::Oidc},
+    field::Field,
+    graph,
+    health::HealthReport,
+};
+
+use self::ui::UiEndpoint;
+
+/// Header machine integrations send their API key in
+const API_KEY_HEADER: &str = "X-API-Key";
+const GRAPHIQL_PAGE: &str = include_str!("web/graphiql.html");
 
 pub async fn run(
     web_address: SocketAddr,
     field: Field,
+    auth: Auth,
+    oidc: Option<Oidc>,
+    ui_dir: Option<PathBuf>,
+    graphiql: bool,
     cancellation_token: CancellationToken,
 ) -> anyhow::Result<()> {
-    let schema = graph::schema::create_schema(field);
-    let app = Route::new()
+    let schema = graph::schema::create_schema(field.clone(), auth.clone());
+    let mut routes = Route::new()
+        .at("/healthz", get(healthz.data(field.clone())))
+        .at("/readyz", get(readyz.data(field.clone())))
+        .at("/metrics", get(metrics.data(field)))
         .at(
             "/api/graphql",
             post(graph::schema::create_graphql_endpoint(schema.clone())),
         )
+        .at(
+            "/api/graphql/ws",
+            get(graph::schema::create_subscription_endpoint(
+                schema.clone(),
+                auth.clone(),
+            )),
+        )
         .at(
             "/api/schema.graphql",
             get(graph::schema::create_sdl_endpoint(schema)),
-        )
+        );
+    if let Some(oidc) = oidc {
+        routes = routes
+            .at("/api/auth/oidc/login", get(oidc_login.data(oidc.clone())))
+            .at("/api/auth/oidc/callback", get(oidc_callback.data(oidc)));
+    }
+    if graphiql {
+        routes = routes.at("/api/graphiql", get(graphiql_page));
+        info!(
+            "Query editor available at http://{}/api/graphiql?key=<api key>",
+            web_address
+        );
+    }
+    if let Some(ui_dir) = ui_dir.as_ref() {
+        info!(
+            "Serving UI files from {} over the built-in ones",
+            ui_dir.display()
+        );
+    }
+    routes = routes.nest("/", UiEndpoint::new(ui_dir));
+    let app = routes
+        .around(move |endpoint, req| authenticate(endpoint, req, auth.clone()))
         .with(
             Cors::new()
                 .allow_method(Method::GET)
-                .allow_method(Method::POST),
+                .allow_method(Method::POST)
+                .allow_header(header::AUTHORIZATION)
+                .allow_header(API_KEY_HEADER),
         );
 
     info!("Web server started on {}", web_address);
+    info!("Admin UI available at http://{}/admin/", web_address);
 
     let server = Server::new(TcpListener::bind(web_address));
 
@@ -46,3 +103,142 @@ pub async fn run(
         .map_err(anyhow::Error::from)?
         .map_err(anyhow::Error::from)
 }
+
+#[derive(Deserialize)]
+struct ApiKeyParams {
+    key: Option<String>,
+}
+
+/// Turns an `X-API-Key` or `Authorization: Bearer <token>` header into a `Session` in the
+/// request's extensions. Page loads cannot set headers, so an API key may also be passed as
+/// `?key=`, the same way unattended displays already open the UI. Requests without credentials
+/// pass through anonymously, while unknown, expired or revoked credentials are rejected so
+/// clients know to sign in again.
+async fn authenticate<E: Endpoint>(
+    endpoint: Arc<E>,
+    mut req: Request,
+    auth: Auth,
+) -> poem::Result<E::Output> {
+    let api_key = req.header(API_KEY_HEADER).map(str::to_string).or_else(|| {
+        req.params::<ApiKeyParams>()
+            .ok()
+            .and_then(|params| params.key)
+    });
+    let token = req
+        .header(header::AUTHORIZATION)
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    let session = match (api_key, token) {
+        (Some(api_key), _) => Some(auth.authenticate_api_key(&api_key)),
+        (None, Some(token)) => Some(auth.authenticate(&token)),
+        (None, None) => None,
+    };
+    if let Some(session) = session {
+        match session {
+            Ok(Some(session)) => {
+                req.extensions_mut().insert(session);
+            }
+            Ok(None) => return Err(poem::Error::from_status(StatusCode::UNAUTHORIZED)),
+            Err(e) => {
+                error!("Error checking credentials: {}", e);
+                return Err(poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR));
+            }
+        }
+    }
+
+    endpoint.call(req).await
+}
+
+/// Liveness probe. Fails only when a tick loop has stalled, which a restart would fix.
+#[handler]
+fn healthz(field: Data<&Field>) -> (StatusCode, Json<HealthReport>) {
+    let report = HealthReport::check(&field);
+    (health_status(report.is_live()), Json(report))
+}
+
+/// Readiness probe. Fails whenever the field cannot talk to driver stations or reach its
+/// database, so event network monitoring can alert on it.
+#[handler]
+fn readyz(field: Data<&Field>) -> (StatusCode, Json<HealthReport>) {
+    let report = HealthReport::check(&field);
+    (health_status(report.is_ready()), Json(report))
+}
+
+fn health_status(healthy: bool) -> StatusCode {
+    if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Prometheus scrape target. Left unauthenticated like most exporters, as it only exposes
+/// field health and no event data.
+#[handler]
+fn metrics(field: Data<&Field>) -> poem::Result<Response> {
+    match field.metrics().render(&field) {
+        Ok(body) => Ok(Response::builder()
+            .content_type("text/plain; version=0.0.4")
+            .body(body)),
+        Err(e) => {
+            error!("Error rendering metrics: {}", e);
+            Err(poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// An in-browser query editor for the API, only served to signed in clients. Open it with an
+/// API key as `/api/graphiql?key=<key>`; the page keeps using that key for its own requests,
+/// which go through the same scope checks as any other client. It loads nothing from outside
+/// the FMS, so it works on an offline field network.
+#[handler]
+fn graphiql_page(session: Option<Data<&Session>>) -> poem::Result<Html<&'static str>> {
+    if session.is_none() {
+        return Err(poem::Error::from_status(StatusCode::UNAUTHORIZED));
+    }
+    Ok(Html(GRAPHIQL_PAGE))
+}
+
+/// Sends the browser to the OpenID Connect provider
+#[handler]
+async fn oidc_login(oidc: Data<&Oidc>) -> poem::Result<Redirect> {
+    match oidc.begin_login().await {
+        Ok(url) => Ok(Redirect::see_other(url)),
+        Err(e) => {
+            error!("Could not start OpenID Connect sign in: {:#}", e);
+            Err(poem::Error::from_status(StatusCode::BAD_GATEWAY))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OidcCallbackParams {
+    state: String,
+    code: Option<String>,
+    error: Option<String>,
+}
+
+/// Where the provider sends the browser back to. Signs the user in and hands the token to the
+/// UI in the URL fragment, which never reaches a server.
+#[handler]
+async fn oidc_callback(
+    oidc: Data<&Oidc>,
+    Query(params): Query<OidcCallbackParams>,
+) -> poem::Result<Redirect> {
+    let Some(code) = params.code else {
+        warn!(
+            "OpenID Connect provider refused sign in: {}",
+            params.error.as_deref().unwrap_or("no reason given")
+        );
+        return Err(poem::Error::from_status(StatusCode::UNAUTHORIZED));
+    };
+
+    match oidc.finish_login(&code, &params.state).await {
+        Ok(token) => Ok(Redirect::see_other(oidc.post_login_redirect(&token))),
+        Err(e) => {
+            warn!("Failed OpenID Connect sign in: {:#}", e);
+            Err(poem::Error::from_status(StatusCode::UNAUTHORIZED))
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::{Component, Path, PathBuf};
+
+use poem::{
+    Endpoint, Request, Response,
+    endpoint::{EmbeddedFilesEndpoint, StaticFilesEndpoint},
+};
+use rust_embed::RustEmbed;
+
+/// The admin, audience and queuing pages in `ui/`, compiled into the binary
+#[derive(RustEmbed)]
+#[folder = "ui/"]
+struct UiAssets;
+
+/// Serves the web UI. Files in the override directory win over the embedded ones, so pages
+/// can be worked on without rebuilding the FMS.
+pub struct UiEndpoint {
+    embedded: EmbeddedFilesEndpoint<UiAssets>,
+    override_dir: Option<(PathBuf, StaticFilesEndpoint)>,
+}
+
+impl UiEndpoint {
+    pub fn new(override_dir: Option<PathBuf>) -> Self {
+        Self {
+            embedded: EmbeddedFilesEndpoint::new(),
+            override_dir: override_dir.map(|dir| {
+                let endpoint = StaticFilesEndpoint::new(&dir)
+                    .index_file("index.html")
+                    .redirect_to_slash_directory();
+                (dir, endpoint)
+            }),
+        }
+    }
+}
+
+impl Endpoint for UiEndpoint {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> poem::Result<Response> {
+        if let Some((dir, endpoint)) = &self.override_dir
+            && is_overridden(dir, req.uri().path())
+        {
+            return endpoint.call(req).await;
+        }
+        self.embedded.call(req).await
+    }
+}
+
+fn is_overridden(dir: &Path, path: &str) -> bool {
+    let relative = Path::new(path.trim_start_matches('/'));
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return false;
+    }
+
+    let file = dir.join(relative);
+    if path.ends_with('/') {
+        file.join("index.html").is_file()
+    } else {
+        file.exists()
+    }
+}
@@ -0,0 +1,64 @@
+// Shared helpers for the pages served by the FMS. Pages authenticate with either a session
+// token (kept in localStorage) or an API key passed as `?key=` for unattended displays.
+
+const TOKEN_KEY = "nevermore.token";
+
+// Tokens handed back by the OpenID Connect callback arrive in the URL fragment
+(function takeTokenFromFragment() {
+    const fragment = new URLSearchParams(window.location.hash.slice(1));
+    const token = fragment.get("token");
+    if (token) {
+        localStorage.setItem(TOKEN_KEY, token);
+        history.replaceState(null, "", window.location.pathname + window.location.search);
+    }
+})();
+
+function authHeaders() {
+    const apiKey = new URLSearchParams(window.location.search).get("key");
+    if (apiKey) {
+        return { "X-API-Key": apiKey };
+    }
+    const token = localStorage.getItem(TOKEN_KEY);
+    return token ? { Authorization: `Bearer ${token}` } : {};
+}
+
+async function graphql(query, variables = {}) {
+    const response = await fetch("/api/graphql", {
+        method: "POST",
+        headers: { "Content-Type": "application/json", ...authHeaders() },
+        body: JSON.stringify({ query, variables }),
+    });
+    if (response.status === 401) {
+        localStorage.removeItem(TOKEN_KEY);
+        throw new Error("Not signed in");
+    }
+    const result = await response.json();
+    if (result.errors) {
+        throw new Error(result.errors.map((error) => error.message).join(", "));
+    }
+    return result.data;
+}
+
+async function signIn(username, password) {
+    const data = await graphql(
+        "mutation($username: String!, $password: String!) { signIn(username: $username, password: $password) { tokenValue } }",
+        { username, password },
+    );
+    localStorage.setItem(TOKEN_KEY, data.signIn.tokenValue);
+}
+
+function signedIn() {
+    return Object.keys(authHeaders()).length > 0;
+}
+
+// Runs `update` now and then every `intervalMs`, reporting errors to `onError`
+function poll(update, intervalMs, onError) {
+    const run = () => update().catch(onError);
+    run();
+    return setInterval(run, intervalMs);
+}
+
+function formatTime(seconds) {
+    const whole = Math.max(0, Math.ceil(seconds));
+    return `${Math.floor(whole / 60)}:${String(whole % 60).padStart(2, "0")}`;
+}